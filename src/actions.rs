@@ -0,0 +1,292 @@
+//! Everything that can be done from Normal mode, by key (see `keymap`) or from the action
+//! palette.
+
+use crate::app::{App, Selection};
+use crate::keys::KeyChord;
+use crossterm::event::{KeyCode, KeyModifiers};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    NewProject,
+    AddRepo,
+    Terminal,
+    NewTab,
+    Editor,
+    RunCommand,
+    PreviousTab,
+    NextTab,
+    SplitSideBySide,
+    SplitStacked,
+    ClosePane,
+    Push,
+    Diff,
+    Remove,
+    ToggleExpand,
+    Sessions,
+    Options,
+    Help,
+    ExportLog,
+    Palette,
+    Quit,
+    Filter,
+    FilterChanges,
+    FilterUnpushed,
+    NextDirty,
+    NextProject,
+    PreviousProject,
+    MoveDown,
+    MoveUp,
+    First,
+    Last,
+    PageDown,
+    PageUp,
+    Collapse,
+    Expand,
+}
+
+impl Action {
+    /// In the order the palette lists them when nothing is typed (and the keymap file
+    /// documents them).
+    pub const ALL: &'static [Action] = &[
+        Action::Terminal,
+        Action::NewTab,
+        Action::RunCommand,
+        Action::Editor,
+        Action::Push,
+        Action::Diff,
+        Action::AddRepo,
+        Action::NewProject,
+        Action::Remove,
+        Action::ToggleExpand,
+        Action::PreviousTab,
+        Action::NextTab,
+        Action::SplitSideBySide,
+        Action::SplitStacked,
+        Action::ClosePane,
+        Action::Sessions,
+        Action::Options,
+        Action::Help,
+        Action::ExportLog,
+        Action::Palette,
+        Action::Quit,
+        Action::Filter,
+        Action::FilterChanges,
+        Action::FilterUnpushed,
+        Action::NextDirty,
+        Action::NextProject,
+        Action::PreviousProject,
+        Action::MoveDown,
+        Action::MoveUp,
+        Action::First,
+        Action::Last,
+        Action::PageDown,
+        Action::PageUp,
+        Action::Collapse,
+        Action::Expand,
+    ];
+
+    /// What the palette shows; some read differently on a project.
+    pub fn label(self, sel: Option<Selection>) -> &'static str {
+        let project = matches!(sel, Some(Selection::Project(_)));
+        match self {
+            Action::NewProject => "New project",
+            Action::AddRepo => "Add repo to project",
+            Action::Terminal if project => "Open terminal at project folder",
+            Action::Terminal => "Open terminal in worktree",
+            Action::NewTab => "New terminal tab",
+            Action::Editor if project => "Open project folder in editor",
+            Action::Editor => "Open worktree in editor",
+            Action::RunCommand if project => "Run command in every worktree",
+            Action::RunCommand => "Run command",
+            Action::PreviousTab => "Previous terminal tab",
+            Action::NextTab => "Next terminal tab",
+            Action::SplitSideBySide => "Split pane side by side",
+            Action::SplitStacked => "Split pane stacked",
+            Action::ClosePane => "Close pane",
+            Action::Push if project => "Push all worktrees",
+            Action::Push => "Push worktree",
+            Action::Diff => "Show diff",
+            Action::Remove if project => "Remove project and its worktrees",
+            Action::Remove => "Remove worktree",
+            Action::ToggleExpand => "Expand / collapse project",
+            Action::Sessions => "Sessions",
+            Action::Options => "Options",
+            Action::Help => "Help",
+            Action::ExportLog => "Export log to /tmp/workman.log",
+            Action::Palette => "Action palette",
+            Action::Quit => "Quit",
+            Action::Filter => "Filter the tree",
+            Action::FilterChanges => "Toggle filter: worktrees with changes",
+            Action::FilterUnpushed => "Toggle filter: worktrees with unpushed commits",
+            Action::NextDirty => "Next worktree with changes",
+            Action::NextProject => "Next project",
+            Action::PreviousProject => "Previous project",
+            Action::MoveDown => "Move down",
+            Action::MoveUp => "Move up",
+            Action::First => "Go to first row",
+            Action::Last => "Go to last row",
+            Action::PageDown => "Page down",
+            Action::PageUp => "Page up",
+            Action::Collapse => "Collapse project / go to project",
+            Action::Expand => "Expand project / go to first worktree",
+        }
+    }
+
+    /// Name used in the keymap file.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::NewProject => "new_project",
+            Action::AddRepo => "add_repo",
+            Action::Terminal => "terminal",
+            Action::NewTab => "new_tab",
+            Action::Editor => "editor",
+            Action::RunCommand => "run_command",
+            Action::PreviousTab => "previous_tab",
+            Action::NextTab => "next_tab",
+            Action::SplitSideBySide => "split_side_by_side",
+            Action::SplitStacked => "split_stacked",
+            Action::ClosePane => "close_pane",
+            Action::Push => "push",
+            Action::Diff => "diff",
+            Action::Remove => "remove",
+            Action::ToggleExpand => "toggle_expand",
+            Action::Sessions => "sessions",
+            Action::Options => "options",
+            Action::Help => "help",
+            Action::ExportLog => "export_log",
+            Action::Palette => "palette",
+            Action::Quit => "quit",
+            Action::Filter => "filter",
+            Action::FilterChanges => "filter_changes",
+            Action::FilterUnpushed => "filter_unpushed",
+            Action::NextDirty => "next_dirty",
+            Action::NextProject => "next_project",
+            Action::PreviousProject => "previous_project",
+            Action::MoveDown => "move_down",
+            Action::MoveUp => "move_up",
+            Action::First => "first",
+            Action::Last => "last",
+            Action::PageDown => "page_down",
+            Action::PageUp => "page_up",
+            Action::Collapse => "collapse",
+            Action::Expand => "expand",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|a| a.name() == s.trim())
+    }
+
+    /// Its key unless the keymap file says otherwise.
+    pub fn default_key(self) -> KeyChord {
+        let (code, modifiers) = match self {
+            Action::NewProject => (KeyCode::Char('n'), KeyModifiers::NONE),
+            Action::AddRepo => (KeyCode::Char('a'), KeyModifiers::NONE),
+            Action::Terminal => (KeyCode::Char('t'), KeyModifiers::NONE),
+            Action::NewTab => (KeyCode::Char('t'), KeyModifiers::SHIFT),
+            Action::Editor => (KeyCode::Char('e'), KeyModifiers::NONE),
+            Action::RunCommand => (KeyCode::Char('r'), KeyModifiers::NONE),
+            Action::PreviousTab => (KeyCode::Char('['), KeyModifiers::NONE),
+            Action::NextTab => (KeyCode::Char(']'), KeyModifiers::NONE),
+            Action::SplitSideBySide => (KeyCode::Char('|'), KeyModifiers::NONE),
+            Action::SplitStacked => (KeyCode::Char('-'), KeyModifiers::NONE),
+            Action::ClosePane => (KeyCode::Char('c'), KeyModifiers::NONE),
+            Action::Push => (KeyCode::Char('p'), KeyModifiers::NONE),
+            Action::Diff => (KeyCode::Char('d'), KeyModifiers::NONE),
+            Action::Remove => (KeyCode::Char('x'), KeyModifiers::NONE),
+            Action::ToggleExpand => (KeyCode::Enter, KeyModifiers::NONE),
+            Action::Sessions => (KeyCode::Char('s'), KeyModifiers::NONE),
+            Action::Options => (KeyCode::Char('o'), KeyModifiers::NONE),
+            Action::Help => (KeyCode::Char('?'), KeyModifiers::NONE),
+            Action::ExportLog => (KeyCode::Char('l'), KeyModifiers::CONTROL),
+            Action::Palette => (KeyCode::Char('p'), KeyModifiers::CONTROL),
+            Action::Quit => (KeyCode::Char('q'), KeyModifiers::NONE),
+            Action::Filter => (KeyCode::Char('/'), KeyModifiers::NONE),
+            Action::FilterChanges => (KeyCode::Char('c'), KeyModifiers::SHIFT),
+            Action::FilterUnpushed => (KeyCode::Char('u'), KeyModifiers::SHIFT),
+            Action::NextDirty => (KeyCode::Char('d'), KeyModifiers::SHIFT),
+            Action::NextProject => (KeyCode::Char('}'), KeyModifiers::NONE),
+            Action::PreviousProject => (KeyCode::Char('{'), KeyModifiers::NONE),
+            Action::MoveDown => (KeyCode::Char('j'), KeyModifiers::NONE),
+            Action::MoveUp => (KeyCode::Char('k'), KeyModifiers::NONE),
+            Action::First => (KeyCode::Char('g'), KeyModifiers::NONE),
+            Action::Last => (KeyCode::Char('g'), KeyModifiers::SHIFT),
+            Action::PageDown => (KeyCode::PageDown, KeyModifiers::NONE),
+            Action::PageUp => (KeyCode::PageUp, KeyModifiers::NONE),
+            Action::Collapse => (KeyCode::Char('h'), KeyModifiers::NONE),
+            Action::Expand => (KeyCode::Char('l'), KeyModifiers::NONE),
+        };
+        KeyChord::new(code, modifiers)
+    }
+
+    /// Whether it does anything right now, with `sel` selected in the tree.
+    pub fn available(self, app: &App, sel: Option<Selection>) -> bool {
+        let tabs = sel.and_then(|sel| app.sessions.get(&sel)).map_or(0, |tabs| tabs.tabs.len());
+        match self {
+            Action::AddRepo => matches!(sel, Some(Selection::Project(_))),
+            // A filtered tree keeps projects expanded
+            Action::ToggleExpand => matches!(sel, Some(Selection::Project(_))) && !app.tree_filter.is_active(),
+            Action::Diff => matches!(sel, Some(Selection::Worktree(_, _))),
+            Action::Terminal | Action::NewTab | Action::Editor | Action::RunCommand | Action::Push
+            | Action::Remove => sel.is_some(),
+            Action::PreviousTab | Action::NextTab => tabs > 1,
+            Action::NextDirty | Action::NextProject | Action::PreviousProject | Action::MoveDown
+            | Action::MoveUp | Action::First | Action::Last | Action::PageDown | Action::PageUp => {
+                !app.config.projects.is_empty()
+            }
+            Action::Collapse => match sel {
+                Some(Selection::Project(_)) => !app.tree_filter.is_active(),
+                Some(Selection::Worktree(_, _)) => true,
+                None => false,
+            },
+            Action::Expand => matches!(sel, Some(Selection::Project(_))),
+            Action::ClosePane => app.panes.len() > 1,
+            Action::ExportLog => app.error_message.is_some() || app.full_error_detail.is_some(),
+            // It is the palette
+            Action::Palette => false,
+            Action::Filter | Action::FilterChanges | Action::FilterUnpushed => !app.config.projects.is_empty(),
+            Action::NewProject | Action::SplitSideBySide | Action::SplitStacked | Action::Sessions
+            | Action::Options | Action::Help | Action::Quit => true,
+        }
+    }
+}
+
+/// Scores `text` against `query` typed in the palette: every query character must appear
+/// in order (case-insensitively). Runs of consecutive characters and matches at word
+/// starts score higher. `None` if it doesn't match.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<u32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut pos = 0;
+    let mut prev: Option<usize> = None;
+    for q in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = pos + text[pos..].iter().position(|&c| c == q)?;
+        score += 1;
+        if prev.is_some_and(|p| p + 1 == found) {
+            score += 4;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        prev = Some(found);
+        pos = found + 1;
+    }
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert!(fuzzy_score("", "Quit").is_some());
+        assert!(fuzzy_score("ntt", "New terminal tab").is_some());
+        assert!(fuzzy_score("tab new", "New terminal tab").is_none());
+        // Word starts and runs beat scattered letters
+        let term = fuzzy_score("term", "Open terminal in worktree").unwrap();
+        let scattered = fuzzy_score("term", "Toggle the remote mirror").unwrap();
+        assert!(term > scattered);
+        assert!(fuzzy_score("PUSH", "Push all worktrees").is_some());
+    }
+}
@@ -1,10 +1,22 @@
-use crate::models::Config;
-use crate::session::Session;
+use crate::actions::{self, Action};
+use crate::commands::{self, UserCommand};
+use crate::daemon;
+use crate::hooks::HookContext;
+use crate::keymap::Keymap;
+use crate::models::{self, Config};
+use crate::multiplexer;
+use crate::panes::Panes;
+use crate::repo_config::RepoConfig;
+use crate::session::{Session, SessionTabs};
+use crate::tasks::{self, CommandRun, OutputNotifier, TaskEvent};
+use crate::terminal_handler::TextSelection;
+use ratatui::layout::Rect;
 use ratatui::widgets::ListState;
 use ratatui::style::{Color, Modifier, Style};
 use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::PathBuf;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
 
 #[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
 pub enum Selection {
@@ -15,17 +27,48 @@ pub enum Selection {
 #[derive(PartialEq)]
 pub enum InputMode {
     Normal,
-    AddingProjectName,   // step 1 of project creation: name (branch derived automatically)
+    AddingProjectName,   // step 1 of project creation: name (branch previewed live)
+    EditingProjectBranch, // step 2 of project creation: confirm or edit the derived branch
     AddingRepo,          // path input + fuzzy suggestions for adding a repo to a project
     ViewingDiff,
     EditingCommitMessage,
     Terminal,
+    NewTabCommand,       // command for a new terminal tab (empty for a shell)
+    Scrollback,          // paging through an attached session's history
+    ScrollbackSearch,    // typing a search query for scrollback
+    Sessions,            // session switcher overlay
+    Commands,            // user command palette for the selected row
+    Actions,             // action palette (Ctrl+P): fuzzy search over every action
+    Filtering,           // typing the tree filter query (/)
+    RenamingSession,     // text entry for the highlighted session's name
     Options,
+    EditingOption,       // text entry for the highlighted Options row
     Help,
     ConfirmDelete,
 }
 
-/// A single entry in the fuzzy suggestion list shown in AddingRepo mode.
+/// Rows of the Options overlay, in display order.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum OptionItem {
+    Multiplexer,
+    PersistentSessions,
+    WorktreeLocation,
+    BranchTemplate,
+    DetachKey,
+    Editor,
+}
+
+impl OptionItem {
+    pub const ALL: &'static [OptionItem] = &[
+        OptionItem::Multiplexer,
+        OptionItem::PersistentSessions,
+        OptionItem::WorktreeLocation,
+        OptionItem::BranchTemplate,
+        OptionItem::DetachKey,
+        OptionItem::Editor,
+    ];
+}
+
 /// Derives a git branch name from a human-readable project name.
 pub fn branch_from_name(name: &str) -> String {
     let raw: String = name.trim().to_lowercase().chars()
@@ -34,6 +77,34 @@ pub fn branch_from_name(name: &str) -> String {
     raw.split('-').filter(|s| !s.is_empty()).collect::<Vec<_>>().join("-")
 }
 
+/// One row of the session switcher. What the shell is doing is sampled when the list is
+/// refreshed; name, activity and exit state are read live from the session.
+pub struct SessionEntry {
+    pub sel: Selection,
+    pub tab: usize,
+    pub command: String,
+    pub cwd: PathBuf,
+}
+
+/// Narrows the project tree. Rows are hidden, never renumbered, so a `Selection` keeps
+/// pointing at the same project or worktree while a filter is on.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TreeFilter {
+    /// Fuzzy-matched against project names, branches and repo names.
+    pub query: String,
+    /// Only worktrees with uncommitted, untracked or unpushed changes.
+    pub changes_only: bool,
+    /// Only worktrees with unpushed commits.
+    pub unpushed_only: bool,
+}
+
+impl TreeFilter {
+    pub fn is_active(&self) -> bool {
+        !self.query.trim().is_empty() || self.changes_only || self.unpushed_only
+    }
+}
+
+/// A single entry in the fuzzy suggestion list shown in AddingRepo mode.
 pub struct FuzzyEntry {
     pub path: PathBuf,
     pub known: bool, // true = previously used in another project
@@ -41,6 +112,7 @@ pub struct FuzzyEntry {
 
 pub struct App {
     pub config: Config,
+    pub keymap: Keymap,
     pub tree_state: ListState,
     pub input_mode: InputMode,
     pub input: String,
@@ -48,13 +120,18 @@ pub struct App {
     pub full_error_detail: Option<String>,
     pub command_output: Vec<String>,
     pub diff_scroll_offset: usize,
-    pub sessions: HashMap<Selection, Session>,
+    pub sessions: HashMap<Selection, SessionTabs>,
     pub terminal_warning: Option<String>,
     pub worktree_status: HashMap<(usize, usize), String>,
+    // Multiplexer sessions named after projects and worktrees, refreshed with the statuses
+    pub mux_sessions: HashSet<String>,
     // Project expand/collapse state
     pub expanded_projects: HashSet<usize>,
+    // What the tree currently shows; see `filtered_worktrees`
+    pub tree_filter: TreeFilter,
     // Project creation state
     pub pending_project_name: String,
+    pub template_cursor: Option<usize>, // None = no template (pick repos one by one)
     // Fuzzy repo picker state (AddingRepo mode)
     pub fuzzy_results: Vec<FuzzyEntry>,
     pub fuzzy_cursor: Option<usize>, // None = cursor at text input; Some(i) = suggestion highlighted
@@ -64,53 +141,648 @@ pub struct App {
     pub options_cursor: usize,
     // Pending destructive delete awaiting confirmation
     pub pending_delete: Option<Selection>,
+    // Worktrees (by path) whose `.workman.toml` setup commands are still running
+    pub setting_up: HashSet<PathBuf>,
+    // Rows (project folders and worktree paths) whose actions wait on hooks still running
+    pub running_hooks: HashSet<PathBuf>,
+    // Background task channel; drained once per frame by the main loop
+    pub task_tx: UnboundedSender<TaskEvent>,
+    pub task_rx: UnboundedReceiver<TaskEvent>,
+    // Shared by every session's reader thread to wake the main loop on output
+    pub output_notifier: OutputNotifier,
+    // Screen areas from the last frame, for mapping mouse events: the whole right
+    // panel, and within it the focused pane
+    pub tree_area: Rect,
+    pub panel_area: Rect,
+    pub output_area: Rect,
+    // Split panes in the right panel; the focused one follows the tree selection
+    pub panes: Panes,
+    // Drag selection over the session in the output pane
+    pub mouse_selection: Option<TextSelection>,
+    // Scrollback search: last query and the line it matched
+    pub scrollback_query: String,
+    pub scrollback_match: Option<usize>,
+    // Session switcher: snapshot of sessions in tree order, and the highlighted row
+    pub session_entries: Vec<SessionEntry>,
+    pub session_cursor: usize,
+    // Command palette: the user commands offered on the selected row, and the highlighted one
+    pub palette_commands: Vec<UserCommand>,
+    pub palette_cursor: usize,
+    // Action palette: actions matching the typed query, best first, and the highlighted one
+    pub action_matches: Vec<Action>,
+    pub action_cursor: usize,
 }
 
 impl App {
     pub fn new() -> App {
         let (config, migration_notice) = Config::load();
+        let (keymap, keymap_notice) = Keymap::load();
         let expanded_projects: HashSet<usize> = (0..config.projects.len()).collect();
         let has_items = !config.projects.is_empty();
+        let (task_tx, task_rx) = mpsc::unbounded_channel();
         let mut app = App {
             config,
+            keymap,
             tree_state: ListState::default(),
             input_mode: InputMode::Normal,
             input: String::new(),
-            error_message: migration_notice,
+            error_message: migration_notice.or(keymap_notice),
             full_error_detail: None,
             command_output: Vec::new(),
             diff_scroll_offset: 0,
             sessions: HashMap::new(),
             terminal_warning: None,
             worktree_status: HashMap::new(),
+            mux_sessions: HashSet::new(),
             expanded_projects,
+            tree_filter: TreeFilter::default(),
             pending_project_name: String::new(),
+            template_cursor: None,
             fuzzy_results: Vec::new(),
             fuzzy_cursor: None,
             adding_to_project: None,
             options_cursor: 0,
             pending_delete: None,
+            setting_up: HashSet::new(),
+            running_hooks: HashSet::new(),
+            output_notifier: OutputNotifier::new(task_tx.clone()),
+            task_tx,
+            task_rx,
+            tree_area: Rect::default(),
+            panel_area: Rect::default(),
+            output_area: Rect::default(),
+            panes: Panes::new(Selection::Project(0)),
+            mouse_selection: None,
+            scrollback_query: String::new(),
+            scrollback_match: None,
+            session_entries: Vec::new(),
+            session_cursor: 0,
+            palette_commands: Vec::new(),
+            palette_cursor: 0,
+            action_matches: Vec::new(),
+            action_cursor: 0,
         };
         if has_items {
             app.tree_state.select(Some(0));
         }
         app.refresh_worktree_status();
+        app.mux_sessions = app.config.settings.multiplexer().list_sessions();
+        if app.config.settings.persistent_sessions {
+            app.reattach_sessions();
+        }
         app
     }
 
     pub fn refresh_worktree_status(&mut self) {
+        let selected = self.get_selected_selection();
         self.worktree_status.clear();
         for (p_idx, project) in self.config.projects.iter().enumerate() {
             for (w_idx, wt) in project.worktrees.iter().enumerate() {
                 self.worktree_status.insert((p_idx, w_idx), wt.get_status());
             }
         }
+        // The status filters hide rows by status, so the cursor's row may have moved
+        if self.tree_filter.is_active() {
+            self.reselect(selected);
+        }
     }
 
     pub fn save_config(&self) {
         let _ = self.config.save();
     }
 
+    // ── Sessions ─────────────────────────────────────────────────────────
+
+    /// Working directory for a terminal opened on `sel`.
+    pub fn session_path(&self, sel: Selection) -> Option<PathBuf> {
+        match sel {
+            Selection::Project(p_idx) => self.config.projects.get(p_idx).map(|p| p.folder.clone()),
+            Selection::Worktree(p_idx, w_idx) => self.config.projects.get(p_idx)
+                .and_then(|p| p.worktrees.get(w_idx))
+                .map(|wt| wt.path.clone()),
+        }
+    }
+
+    /// The active tab on `sel`, if any.
+    pub fn session(&self, sel: Selection) -> Option<&Session> {
+        self.sessions.get(&sel).map(|tabs| tabs.active())
+    }
+
+    pub fn session_mut(&mut self, sel: Selection) -> Option<&mut Session> {
+        self.sessions.get_mut(&sel).map(|tabs| tabs.active_mut())
+    }
+
+    /// Starts a process for a tab on `sel`: in the session daemon when sessions are
+    /// persistent, otherwise owned by workman.
+    fn new_session(&self, sel: Selection, command: Option<String>, width: u16, height: u16) -> anyhow::Result<Session> {
+        let path = self.session_path(sel).ok_or_else(|| anyhow::anyhow!("Nothing selected"))?;
+        let notifier = self.output_notifier.clone();
+        if self.config.settings.persistent_sessions {
+            Session::new_persistent(&daemon::socket_path(), path, command, width, height, notifier)
+        } else {
+            Session::new(path, command, width, height, notifier)
+        }
+    }
+
+    /// Opens a new tab on `sel` running `command` (or a shell) and makes it active.
+    pub fn start_session(&mut self, sel: Selection, command: Option<String>, width: u16, height: u16) -> anyhow::Result<()> {
+        let session = self.new_session(sel, command, width, height)?;
+        match self.sessions.get_mut(&sel) {
+            Some(tabs) => tabs.push(session),
+            None => { self.sessions.insert(sel, SessionTabs::new(session)); }
+        }
+        Ok(())
+    }
+
+    /// Replaces the active tab on `sel` with a fresh process running the same command,
+    /// keeping its name and position.
+    pub fn restart_session(&mut self, sel: Selection, width: u16, height: u16) -> anyhow::Result<()> {
+        let old = self.session(sel).ok_or_else(|| anyhow::anyhow!("No session to restart"))?;
+        let (command, name) = (old.command.clone(), old.name.clone());
+        let mut session = self.new_session(sel, command, width, height)?;
+        session.set_name(name);
+        if let Some(tabs) = self.sessions.get_mut(&sel) {
+            *tabs.active_mut() = session;
+        }
+        Ok(())
+    }
+
+    /// Picks up the sessions an earlier workman left running in the daemon, as tabs on
+    /// the rows they were opened on. Sessions whose row is gone are ended.
+    fn reattach_sessions(&mut self) {
+        let socket = daemon::socket_path();
+        let infos = match daemon::list(&socket) {
+            Ok(infos) => infos,
+            Err(e) => {
+                self.error_message = Some(format!("Could not reach the session daemon: {}", e));
+                return;
+            }
+        };
+        let rows: Vec<Selection> = self.config.projects.iter().enumerate()
+            .flat_map(|(p_idx, project)| {
+                std::iter::once(Selection::Project(p_idx))
+                    .chain((0..project.worktrees.len()).map(move |w_idx| Selection::Worktree(p_idx, w_idx)))
+            })
+            .collect();
+        for info in infos {
+            let row = rows.iter().copied().find(|sel| self.session_path(*sel).as_ref() == Some(&info.dir));
+            match Session::reattach(&socket, info.id, self.output_notifier.clone()) {
+                Ok(session) => match row {
+                    Some(sel) => match self.sessions.get_mut(&sel) {
+                        Some(tabs) => tabs.push(session),
+                        None => { self.sessions.insert(sel, SessionTabs::new(session)); }
+                    },
+                    None => drop(session),
+                },
+                Err(e) => self.error_message = Some(format!("Failed to reattach session: {}", e)),
+            }
+        }
+        for tabs in self.sessions.values_mut() {
+            tabs.active = 0;
+        }
+    }
+
+    /// Leaves persistent sessions running in the daemon when workman quits.
+    pub fn detach_sessions(&mut self) {
+        for tabs in self.sessions.values_mut() {
+            tabs.tabs.iter_mut().for_each(Session::detach);
+        }
+    }
+
+    /// Closes tab `tab` on `sel`, dropping the row's entry with its last tab.
+    pub fn close_session(&mut self, sel: Selection, tab: usize) {
+        if let Some(tabs) = self.sessions.get_mut(&sel)
+            && !tabs.close(tab)
+        {
+            self.sessions.remove(&sel);
+        }
+    }
+
+    /// Reaps tabs whose process has exited, returning whether any had. If the attached
+    /// one exited, explains how to restart or close it.
+    pub fn reap_sessions(&mut self) -> bool {
+        let selected = self.get_selected_selection();
+        let mut reaped = false;
+        for (sel, tabs) in self.sessions.iter_mut() {
+            let active = tabs.active;
+            for (idx, session) in tabs.tabs.iter_mut().enumerate() {
+                let Some(code) = session.poll_exit() else { continue };
+                reaped = true;
+                if Some(*sel) == selected
+                    && idx == active
+                    && matches!(self.input_mode, InputMode::Terminal | InputMode::Scrollback | InputMode::ScrollbackSearch)
+                {
+                    self.input_mode = InputMode::Terminal;
+                    self.terminal_warning = Some(format!(
+                        "Process exited with code {}. Enter restarts it; any other key closes the tab.", code
+                    ));
+                }
+            }
+        }
+        reaped
+    }
+
+    /// Drops the sessions of project `p_idx` (killing their shells) and shifts the keys
+    /// of later projects down, matching the removal from `config.projects`.
+    pub fn remove_project_sessions(&mut self, p_idx: usize) {
+        if let Some(project) = self.config.projects.get(p_idx) {
+            let rows = std::iter::once(Selection::Project(p_idx))
+                .chain((0..project.worktrees.len()).map(|w_idx| Selection::Worktree(p_idx, w_idx)));
+            rows.for_each(|sel| self.kill_mux_session(sel));
+        }
+        self.rekey_sessions(|sel| match sel {
+            Selection::Project(p) if p == p_idx => None,
+            Selection::Worktree(p, _) if p == p_idx => None,
+            Selection::Project(p) if p > p_idx => Some(Selection::Project(p - 1)),
+            Selection::Worktree(p, w) if p > p_idx => Some(Selection::Worktree(p - 1, w)),
+            other => Some(other),
+        });
+    }
+
+    /// Drops the sessions of worktree `w_idx` and shifts its later siblings down.
+    pub fn remove_worktree_session(&mut self, p_idx: usize, w_idx: usize) {
+        self.kill_mux_session(Selection::Worktree(p_idx, w_idx));
+        self.rekey_sessions(|sel| match sel {
+            Selection::Worktree(p, w) if p == p_idx && w == w_idx => None,
+            Selection::Worktree(p, w) if p == p_idx && w > w_idx => Some(Selection::Worktree(p, w - 1)),
+            other => Some(other),
+        });
+    }
+
+    /// Name of the multiplexer session for `sel` (whether or not it exists).
+    pub fn mux_session_name(&self, sel: Selection) -> Option<String> {
+        match sel {
+            Selection::Project(p_idx) => {
+                let project = self.config.projects.get(p_idx)?;
+                Some(multiplexer::session_name(&project.name, None))
+            }
+            Selection::Worktree(p_idx, w_idx) => {
+                let project = self.config.projects.get(p_idx)?;
+                let wt = project.worktrees.get(w_idx)?;
+                Some(multiplexer::session_name(&project.name, Some(&wt.repo_name)))
+            }
+        }
+    }
+
+    /// Ends the multiplexer session of a project or worktree being removed, if it has one.
+    fn kill_mux_session(&mut self, sel: Selection) {
+        let multiplexer = self.config.settings.multiplexer();
+        if let Some(name) = self.mux_session_name(sel)
+            && multiplexer.is_external()
+        {
+            multiplexer.kill_session(&name);
+            self.mux_sessions.remove(&name);
+        }
+    }
+
+    /// Whether an action on `sel` is waiting for hooks: on the row itself, its project,
+    /// or (for a project) any of its worktrees.
+    pub fn hooks_running(&self, sel: Selection) -> bool {
+        let (p_idx, worktree) = match sel {
+            Selection::Project(p_idx) => (p_idx, None),
+            Selection::Worktree(p_idx, w_idx) => (p_idx, Some(w_idx)),
+        };
+        let Some(project) = self.config.projects.get(p_idx) else { return false };
+        self.running_hooks.contains(&project.folder)
+            || project.worktrees.iter().enumerate()
+                .filter(|(w_idx, _)| worktree.is_none_or(|w| w == *w_idx))
+                .any(|(_, wt)| self.running_hooks.contains(&wt.path))
+    }
+
+    /// Tree row suffix counting the live sessions opened on `sel`, and marking rows
+    /// with a multiplexer session.
+    fn session_badge(&self, sel: Selection) -> String {
+        let live = self.sessions.get(&sel)
+            .map(|tabs| tabs.tabs.iter().filter(|s| s.exit_code.is_none()).count())
+            .unwrap_or(0);
+        let mux = self.mux_session_name(sel).is_some_and(|name| self.mux_sessions.contains(&name));
+        let sessions = match live {
+            0 => String::new(),
+            1 => "  [1 session]".to_string(),
+            n => format!("  [{} sessions]", n),
+        };
+        if mux {
+            format!("{}  [{}]", sessions, self.config.settings.multiplexer().name())
+        } else {
+            sessions
+        }
+    }
+
+    /// Label for tab `tab` on `sel`, e.g. `project / repo · dev server`.
+    pub fn session_label(&self, sel: Selection, tab: usize) -> String {
+        let tab_label = self.sessions.get(&sel)
+            .and_then(|tabs| tabs.tabs.get(tab))
+            .map(|s| s.label())
+            .unwrap_or_default();
+        format!("{} · {}", self.tree_label(sel), tab_label)
+    }
+
+    /// `project / repo` for a worktree, or just the project name.
+    pub fn tree_label(&self, sel: Selection) -> String {
+        match sel {
+            Selection::Project(p_idx) => self.config.projects.get(p_idx)
+                .map(|p| p.name.clone())
+                .unwrap_or_default(),
+            Selection::Worktree(p_idx, w_idx) => self.config.projects.get(p_idx)
+                .and_then(|p| p.worktrees.get(w_idx).map(|wt| format!("{} / {}", p.name, wt.repo_name)))
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Re-samples the session switcher list, in tree order.
+    pub fn refresh_session_entries(&mut self) {
+        let mut entries: Vec<SessionEntry> = self.sessions.iter()
+            .flat_map(|(sel, tabs)| tabs.tabs.iter().enumerate().map(|(tab, session)| SessionEntry {
+                sel: *sel,
+                tab,
+                command: if session.exit_code.is_some() {
+                    String::new()
+                } else {
+                    session.foreground_command().unwrap_or_default()
+                },
+                cwd: session.current_dir(),
+            }))
+            .collect();
+        entries.sort_by_key(|e| match e.sel {
+            Selection::Project(p) => (p, 0, e.tab),
+            Selection::Worktree(p, w) => (p, w + 1, e.tab),
+        });
+        self.session_entries = entries;
+        self.session_cursor = self.session_cursor.min(self.session_entries.len().saturating_sub(1));
+    }
+
+    /// Moves the tree cursor to `sel`, expanding its project if needed and dropping the
+    /// tree filter if it hides it.
+    pub fn select_in_tree(&mut self, sel: Selection) {
+        if let Selection::Worktree(p_idx, _) = sel {
+            self.expanded_projects.insert(p_idx);
+        }
+        if !self.tree_items_contain(sel) {
+            self.tree_filter = TreeFilter::default();
+        }
+        if let Some(idx) = self.get_tree_items().iter().position(|(_, s, _)| *s == sel) {
+            self.tree_state.select(Some(idx));
+        }
+    }
+
+    fn tree_items_contain(&self, sel: Selection) -> bool {
+        self.get_tree_items().iter().any(|(_, s, _)| *s == sel)
+    }
+
+    /// Replaces the tree filter, keeping the cursor on the same row if it is still shown
+    /// and moving it to the first row otherwise.
+    pub fn set_tree_filter(&mut self, filter: TreeFilter) {
+        let selected = self.get_selected_selection();
+        self.tree_filter = filter;
+        self.reselect(selected);
+    }
+
+    /// Puts the cursor back on `selected` after the rows shown changed, or on the first
+    /// row if it is now hidden.
+    fn reselect(&mut self, selected: Option<Selection>) {
+        let items = self.get_tree_items();
+        let idx = selected
+            .and_then(|sel| items.iter().position(|(_, s, _)| *s == sel))
+            .or(if items.is_empty() { None } else { Some(0) });
+        self.tree_state.select(idx);
+    }
+
+    /// Which of project `p_idx`'s worktrees the tree filter lets through, or `None` to hide
+    /// the project. A query matching the project name or branch keeps all its worktrees;
+    /// otherwise only those whose repo name matches. The status filters apply to
+    /// worktrees, and hide projects left with none.
+    pub fn filtered_worktrees(&self, p_idx: usize) -> Option<Vec<usize>> {
+        let project = self.config.projects.get(p_idx)?;
+        let filter = &self.tree_filter;
+        if !filter.is_active() {
+            return Some((0..project.worktrees.len()).collect());
+        }
+        let query = filter.query.trim();
+        let matches = |text: &str| query.is_empty() || actions::fuzzy_score(query, text).is_some();
+        let project_matches = matches(&project.name) || matches(&project.branch);
+        let shown: Vec<usize> = project.worktrees.iter().enumerate()
+            .filter(|(w_idx, wt)| {
+                let status = self.worktree_status.get(&(p_idx, *w_idx));
+                (project_matches || matches(&wt.repo_name))
+                    && (!filter.changes_only || status.is_some_and(|s| models::status_has_changes(s)))
+                    && (!filter.unpushed_only || status.is_some_and(|s| models::status_has_unpushed(s)))
+            })
+            .map(|(w_idx, _)| w_idx)
+            .collect();
+        let by_status = filter.changes_only || filter.unpushed_only;
+        if shown.is_empty() && (by_status || !project_matches) {
+            None
+        } else {
+            Some(shown)
+        }
+    }
+
+    fn rekey_sessions(&mut self, f: impl Fn(Selection) -> Option<Selection>) {
+        self.sessions = std::mem::take(&mut self.sessions)
+            .into_iter()
+            .filter_map(|(sel, session)| f(sel).map(|new| (new, session)))
+            .collect();
+        self.panes.remap(&f);
+    }
+
+    /// Columns and rows inside the focused pane's border, for starting a session there.
+    pub fn pane_size(&self) -> (u16, u16) {
+        (self.output_area.width.saturating_sub(2).max(1), self.output_area.height.saturating_sub(2).max(1))
+    }
+
+    /// Sizes the session shown in each pane to the inside of that pane. A row shown in
+    /// several panes takes the focused pane's size, else the first one's.
+    pub fn resize_panes(&mut self) {
+        let mut layout = self.panes.layout(self.panel_area);
+        if self.panes.focused < layout.len() {
+            let focused = layout.remove(self.panes.focused);
+            layout.insert(0, focused);
+        }
+        let mut seen = HashSet::new();
+        for (sel, area) in layout {
+            let (width, height) = (area.width.saturating_sub(2), area.height.saturating_sub(2));
+            if !seen.insert(sel) || width == 0 || height == 0 {
+                continue;
+            }
+            if let Some(session) = self.session_mut(sel) {
+                let _ = session.resize(width, height);
+            }
+        }
+    }
+
+    /// Focuses split pane `idx` and selects what it shows in the tree. Leaves the
+    /// terminal if the pane has no running session to stay attached to.
+    pub fn focus_pane(&mut self, idx: usize) {
+        let Some(sel) = self.panes.selection(idx) else { return };
+        self.panes.focused = idx;
+        self.select_in_tree(sel);
+        self.mouse_selection = None;
+        if self.input_mode == InputMode::Terminal && self.session(sel).is_none_or(|s| s.exit_code.is_some()) {
+            self.input_mode = InputMode::Normal;
+        }
+    }
+
+    // ── User commands ────────────────────────────────────────────────────
+
+    /// Worktrees a user command on `sel` runs in: the worktree, or every worktree of the project.
+    fn command_worktrees(&self, sel: Selection) -> Vec<(usize, usize)> {
+        match sel {
+            Selection::Project(p_idx) => {
+                let count = self.config.projects.get(p_idx).map_or(0, |p| p.worktrees.len());
+                (0..count).map(|w_idx| (p_idx, w_idx)).collect()
+            }
+            Selection::Worktree(p_idx, w_idx) => vec![(p_idx, w_idx)],
+        }
+    }
+
+    /// The commands of repo `repo_name`'s `.workman.toml`; an unreadable file offers none.
+    fn repo_commands(&self, repo_name: &str) -> Vec<UserCommand> {
+        self.config.repos.iter()
+            .find(|r| r.name == repo_name)
+            .and_then(|r| RepoConfig::load(&r.path).ok())
+            .map(|config| config.commands)
+            .unwrap_or_default()
+    }
+
+    /// Commands the palette offers on `sel`: the global ones plus the repo's own. A project
+    /// offers those of all its worktrees, even ones only some of them define.
+    pub fn commands_for(&self, sel: Selection) -> Vec<UserCommand> {
+        self.command_worktrees(sel).into_iter().fold(self.config.settings.commands.clone(), |acc, (p_idx, w_idx)| {
+            commands::merge(&acc, &self.repo_commands(&self.config.projects[p_idx].worktrees[w_idx].repo_name))
+        })
+    }
+
+    /// Runs user command `name` on `sel`, in every worktree that defines it. Terminal
+    /// commands open a tab on each worktree; the rest run in the background, streaming
+    /// into `command_output` until `TaskEvent::CommandFinished` sums them up. Returns how
+    /// many tabs were opened.
+    pub fn run_user_command(&mut self, sel: Selection, name: &str) -> anyhow::Result<usize> {
+        let global = self.config.settings.commands.clone();
+        let targets: Vec<_> = self.command_worktrees(sel).into_iter()
+            .filter_map(|(p_idx, w_idx)| {
+                let wt = &self.config.projects[p_idx].worktrees[w_idx];
+                commands::merge(&global, &self.repo_commands(&wt.repo_name)).into_iter()
+                    .find(|c| c.name == name)
+                    .map(|command| ((p_idx, w_idx), command))
+            })
+            .collect();
+        if targets.is_empty() {
+            return Err(anyhow::anyhow!("No worktree here has a '{}' command", name));
+        }
+        if targets.iter().any(|(_, c)| c.terminal) && self.config.settings.multiplexer().is_external() {
+            return Err(anyhow::anyhow!("Terminal commands need the built-in terminal; pick it as the terminal backend in Options."));
+        }
+
+        let (width, height) = self.pane_size();
+        let mut runs = Vec::new();
+        let mut opened = 0;
+        for ((p_idx, w_idx), command) in targets {
+            if command.terminal {
+                self.start_session(Selection::Worktree(p_idx, w_idx), Some(command.command), width, height)?;
+                opened += 1;
+                continue;
+            }
+            let project = &self.config.projects[p_idx];
+            let wt = &project.worktrees[w_idx];
+            let env = HookContext {
+                project: &project.name,
+                repo: &wt.repo_name,
+                branch: &project.branch,
+                worktree: &wt.path,
+            }.vars();
+            runs.push(CommandRun { label: wt.repo_name.clone(), path: wt.path.clone(), command: command.command, env });
+        }
+        if !runs.is_empty() {
+            self.command_output.clear();
+            self.error_message = None;
+            self.full_error_detail = None;
+            tasks::spawn_command(name.to_string(), runs, self.task_tx.clone());
+        }
+        Ok(opened)
+    }
+
+    /// Applies a background task event (setup output, completions). Finished hook runs go
+    /// to `event_handler::handle_task_event`, which resumes the action they held up.
+    pub fn handle_task_event(&mut self, event: TaskEvent) {
+        match event {
+            TaskEvent::Output { label, line } => {
+                self.command_output.push(format!("[{}] {}", label, line));
+            }
+            TaskEvent::SetupFinished { path, label, success } => {
+                self.setting_up.remove(&path);
+                self.refresh_worktree_status();
+                if success {
+                    self.command_output.push(format!("[{}] setup complete", label));
+                } else {
+                    self.error_message = Some(format!("Setup failed for [{}] (see output)", label));
+                    self.full_error_detail = Some(self.command_output.join("\n"));
+                }
+            }
+            // A shell hanging up also shows up as output; notice it straight away
+            TaskEvent::SessionOutput => {
+                self.reap_sessions();
+            }
+            TaskEvent::CommandFinished { name, results } => {
+                self.refresh_worktree_status();
+                let failed: Vec<&str> = results.iter()
+                    .filter(|(_, success)| !success)
+                    .map(|(label, _)| label.as_str())
+                    .collect();
+                self.command_output.push(format!(
+                    "{}: {}/{} succeeded",
+                    name,
+                    results.len() - failed.len(),
+                    results.len()
+                ));
+                if !failed.is_empty() {
+                    self.error_message = Some(format!("'{}' failed in [{}] (see output)", name, failed.join("], [")));
+                    self.full_error_detail = Some(self.command_output.join("\n"));
+                }
+            }
+            TaskEvent::MultiplexerSessions(sessions) => {
+                self.mux_sessions = sessions;
+            }
+            TaskEvent::HooksFinished { .. } => {}
+            TaskEvent::StatusRefreshed(statuses) => {
+                let selected = self.get_selected_selection();
+                for (key @ (p_idx, w_idx), path, status) in statuses {
+                    let current = self.config.projects.get(p_idx).and_then(|p| p.worktrees.get(w_idx));
+                    if current.is_some_and(|wt| wt.path == path) {
+                        self.worktree_status.insert(key, status);
+                    }
+                }
+                if self.tree_filter.is_active() {
+                    self.reselect(selected);
+                }
+            }
+        }
+    }
+
+    /// Refreshes worktree statuses in the background, picking up changes made from
+    /// terminals; results arrive as `TaskEvent::StatusRefreshed`.
+    pub fn spawn_status_refresh(&self) {
+        let worktrees = self.config.projects.iter().enumerate()
+            .flat_map(|(p_idx, p)| {
+                p.worktrees.iter().enumerate().map(move |(w_idx, wt)| ((p_idx, w_idx), wt.clone()))
+            })
+            .collect();
+        tasks::spawn_status_refresh(worktrees, self.task_tx.clone());
+        let multiplexer = self.config.settings.multiplexer();
+        if multiplexer.is_external() {
+            tasks::spawn_multiplexer_refresh(multiplexer, self.task_tx.clone());
+        }
+    }
+
+    /// Branch for a new project called `name`: the selected template's prefix followed by
+    /// the configured branch naming. Errors when the ticket pattern is invalid.
+    pub fn derive_branch(&self, name: &str) -> anyhow::Result<String> {
+        let prefix = self.template_cursor
+            .and_then(|i| self.config.templates.get(i))
+            .and_then(|t| t.branch_prefix.as_deref())
+            .unwrap_or_default();
+        self.config.settings.branch_naming.branch_for(name, prefix)
+    }
+
     /// Recomputes fuzzy suggestions from previously used repos + filesystem directories.
     /// Call whenever `self.input` changes while in AddingRepo mode.
     pub fn update_fuzzy_results(&mut self) {
@@ -124,12 +796,11 @@ impl App {
         let is_path_input = self.input.contains('/');
         for repo in &self.config.repos {
             // Skip repos already wired into the target project
-            if let Some(p_idx) = self.adding_to_project {
-                if p_idx < self.config.projects.len()
-                    && self.config.projects[p_idx].worktrees.iter().any(|wt| wt.repo_name == repo.name)
-                {
-                    continue;
-                }
+            if let Some(p_idx) = self.adding_to_project
+                && p_idx < self.config.projects.len()
+                && self.config.projects[p_idx].worktrees.iter().any(|wt| wt.repo_name == repo.name)
+            {
+                continue;
             }
             let matches = if is_path_input || query.is_empty() {
                 true // always show when navigating filesystem or nothing typed
@@ -185,36 +856,76 @@ impl App {
         self.fuzzy_results = results;
     }
 
+    /// Recomputes the action palette's list from `self.input`: the actions available on
+    /// the selected row whose label fuzzy-matches it, best match first.
+    pub fn update_action_matches(&mut self) {
+        let sel = self.get_selected_selection();
+        let mut scored: Vec<(u32, Action)> = Action::ALL.iter()
+            .filter(|a| a.available(self, sel))
+            .filter_map(|&a| actions::fuzzy_score(&self.input, a.label(sel)).map(|score| (score, a)))
+            .collect();
+        // Stable, so equal scores keep the `Action::ALL` order
+        scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        self.action_matches = scored.into_iter().map(|(_, a)| a).collect();
+    }
+
     /// Builds the flat list of items for the left-panel tree.
     pub fn get_tree_items(&self) -> Vec<(String, Selection, Style)> {
         let mut items = Vec::new();
 
+        let filtering = self.tree_filter.is_active();
         for (p_idx, project) in self.config.projects.iter().enumerate() {
-            let is_expanded = self.expanded_projects.contains(&p_idx);
+            let Some(shown) = self.filtered_worktrees(p_idx) else {
+                continue;
+            };
+            // Matches are shown even in collapsed projects
+            let is_expanded = self.expanded_projects.contains(&p_idx) || (filtering && !shown.is_empty());
             let prefix = if is_expanded { "▼" } else { "▶" };
             items.push((
-                format!("{} {}", prefix, project.name),
+                format!(
+                    "{} {}{}{}",
+                    prefix,
+                    project.name,
+                    self.session_badge(Selection::Project(p_idx)),
+                    if self.running_hooks.contains(&project.folder) { "  running hooks..." } else { "" },
+                ),
                 Selection::Project(p_idx),
                 Style::default().add_modifier(Modifier::BOLD),
             ));
 
             if is_expanded {
-                let wt_count = project.worktrees.len();
-                for (w_idx, wt) in project.worktrees.iter().enumerate() {
-                    let tree_sym = if w_idx == wt_count - 1 { "└──" } else { "├──" };
-                    let status_str = self.worktree_status
-                        .get(&(p_idx, w_idx))
-                        .map(|s| s.as_str())
-                        .unwrap_or("...");
-                    let style = if status_str == "clean" {
+                let last = shown.last().copied();
+                for (w_idx, wt) in project.worktrees.iter().enumerate().filter(|(w_idx, _)| shown.contains(w_idx)) {
+                    let tree_sym = if Some(w_idx) == last { "└──" } else { "├──" };
+                    let status_str = if self.setting_up.contains(&wt.path) {
+                        "setting up..."
+                    } else if self.running_hooks.contains(&wt.path) {
+                        "running hooks..."
+                    } else {
+                        self.worktree_status
+                            .get(&(p_idx, w_idx))
+                            .map(|s| s.as_str())
+                            .unwrap_or("...")
+                    };
+                    let style = if status_str == "setting up..." || status_str == "running hooks..." {
+                        Style::default().fg(Color::Yellow)
+                    } else if status_str == "clean" {
                         Style::default().fg(Color::Green)
                     } else if status_str == "..." {
                         Style::default().fg(Color::DarkGray)
                     } else {
                         Style::default().fg(Color::Red)
                     };
+                    let sel = Selection::Worktree(p_idx, w_idx);
+                    let exited = self.session(sel)
+                        .and_then(|s| s.exit_code)
+                        .map(|code| format!("  (shell exited: {})", code))
+                        .unwrap_or_default();
                     items.push((
-                        format!("  {} [{}]  {}  {}", tree_sym, wt.repo_name, project.branch, status_str),
+                        format!(
+                            "  {} [{}]  {}  {}{}{}",
+                            tree_sym, wt.repo_name, project.branch, status_str, self.session_badge(sel), exited
+                        ),
                         Selection::Worktree(p_idx, w_idx),
                         style,
                     ));
@@ -258,8 +969,110 @@ impl App {
         self.full_error_detail = None;
     }
 
+    /// Moves the cursor to tree row `idx`, clamped to the last row.
+    fn select_row(&mut self, idx: usize) {
+        let len = self.get_tree_items().len();
+        if len == 0 {
+            return;
+        }
+        self.tree_state.select(Some(idx.min(len - 1)));
+        self.error_message = None;
+        self.full_error_detail = None;
+    }
+
+    pub fn select_first(&mut self) {
+        self.select_row(0);
+    }
+
+    pub fn select_last(&mut self) {
+        self.select_row(usize::MAX);
+    }
+
+    /// Moves `rows` down (up if negative) without wrapping around.
+    pub fn move_by(&mut self, rows: isize) {
+        let current = self.tree_state.selected().unwrap_or(0);
+        self.select_row(current.saturating_add_signed(rows));
+    }
+
+    /// Rows visible in the tree, for paging.
+    pub fn tree_page(&self) -> usize {
+        self.tree_area.height.saturating_sub(2).max(1) as usize
+    }
+
+    /// Collapses the selected project; on a worktree, moves up to its project. While the
+    /// tree is filtered, projects stay expanded.
+    pub fn collapse_selected(&mut self) {
+        match self.get_selected_selection() {
+            Some(Selection::Project(p_idx)) if !self.tree_filter.is_active() => {
+                self.expanded_projects.remove(&p_idx);
+            }
+            Some(Selection::Worktree(p_idx, _)) => self.select_in_tree(Selection::Project(p_idx)),
+            Some(Selection::Project(_)) | None => {}
+        }
+    }
+
+    /// Expands the selected project, or moves into its first worktree if it already is.
+    pub fn expand_selected(&mut self) {
+        if let Some(Selection::Project(p_idx)) = self.get_selected_selection()
+            && !self.expanded_projects.insert(p_idx)
+            && !self.config.projects[p_idx].worktrees.is_empty()
+        {
+            self.select_in_tree(Selection::Worktree(p_idx, 0));
+        }
+    }
+
+    /// Moves to the next project row below the cursor (`forward`) or the previous one
+    /// above it, wrapping around.
+    pub fn jump_project(&mut self, forward: bool) {
+        let rows: Vec<usize> = self.get_tree_items().iter().enumerate()
+            .filter(|(_, (_, sel, _))| matches!(sel, Selection::Project(_)))
+            .map(|(i, _)| i)
+            .collect();
+        let current = self.tree_state.selected().unwrap_or(0);
+        let target = if forward {
+            rows.iter().find(|&&i| i > current).or(rows.first())
+        } else {
+            rows.iter().rev().find(|&&i| i < current).or(rows.last())
+        };
+        if let Some(&idx) = target {
+            self.select_row(idx);
+        }
+    }
+
+    /// Selects the next worktree after the cursor with uncommitted or unpushed changes,
+    /// expanding its project and wrapping around. Collapsed projects are searched too,
+    /// rows hidden by the tree filter are not.
+    pub fn next_dirty_worktree(&mut self) {
+        // Tree order: a project before its worktrees
+        let position = |sel: Selection| match sel {
+            Selection::Project(p_idx) => (p_idx, 0),
+            Selection::Worktree(p_idx, w_idx) => (p_idx, w_idx + 1),
+        };
+        let dirty: Vec<(usize, usize)> = self.config.projects.iter().enumerate()
+            .flat_map(|(p_idx, p)| (0..p.worktrees.len()).map(move |w_idx| (p_idx, w_idx)))
+            .filter(|key| self.worktree_status.get(key).is_some_and(|s| models::status_has_changes(s)))
+            .filter(|&(p_idx, w_idx)| self.filtered_worktrees(p_idx).is_some_and(|shown| shown.contains(&w_idx)))
+            .collect();
+        let current = self.get_selected_selection().map_or((0, 0), position);
+        let next = dirty.iter()
+            .find(|&&(p_idx, w_idx)| position(Selection::Worktree(p_idx, w_idx)) > current)
+            .or(dirty.first());
+        match next {
+            Some(&(p_idx, w_idx)) => {
+                self.select_in_tree(Selection::Worktree(p_idx, w_idx));
+                self.error_message = None;
+                self.full_error_detail = None;
+            }
+            None => self.error_message = Some("No worktree has changes.".to_string()),
+        }
+    }
+
     /// Toggles expand/collapse for a project.
     pub fn toggle_project_expand(&mut self, p_idx: usize) {
+        // A filtered tree shows every project with matches expanded
+        if self.tree_filter.is_active() {
+            return;
+        }
         if self.expanded_projects.contains(&p_idx) {
             self.expanded_projects.remove(&p_idx);
         } else {
@@ -272,11 +1085,20 @@ impl App {
 mod tests {
     use super::*;
     use crate::models::{Project, ProjectWorktree, Repo};
+    use crate::multiplexer::Multiplexer;
     use std::path::PathBuf;
 
+    fn drain_task_events(app: &mut App) {
+        while let Ok(event) = app.task_rx.try_recv() {
+            app.handle_task_event(event);
+        }
+    }
+
     fn make_test_app() -> App {
+        let (task_tx, task_rx) = mpsc::unbounded_channel();
         App {
             config: Config::default(),
+            keymap: Keymap::default(),
             tree_state: ListState::default(),
             input_mode: InputMode::Normal,
             input: String::new(),
@@ -287,13 +1109,34 @@ mod tests {
             sessions: HashMap::new(),
             terminal_warning: None,
             worktree_status: HashMap::new(),
+            mux_sessions: HashSet::new(),
             expanded_projects: HashSet::new(),
+            tree_filter: TreeFilter::default(),
             pending_project_name: String::new(),
+            template_cursor: None,
             fuzzy_results: Vec::new(),
             fuzzy_cursor: None,
             adding_to_project: None,
             options_cursor: 0,
             pending_delete: None,
+            setting_up: HashSet::new(),
+            running_hooks: HashSet::new(),
+            output_notifier: OutputNotifier::new(task_tx.clone()),
+            task_tx,
+            task_rx,
+            tree_area: Rect::default(),
+            panel_area: Rect::default(),
+            output_area: Rect::default(),
+            panes: Panes::new(Selection::Project(0)),
+            mouse_selection: None,
+            scrollback_query: String::new(),
+            scrollback_match: None,
+            session_entries: Vec::new(),
+            session_cursor: 0,
+            palette_commands: Vec::new(),
+            palette_cursor: 0,
+            action_matches: Vec::new(),
+            action_cursor: 0,
         }
     }
 
@@ -361,6 +1204,415 @@ mod tests {
         // Worktree label should contain repo name and branch
         assert!(items[1].0.contains("frontend"));
         assert!(items[1].0.contains("feat/my-feature"));
+        // Rows with a multiplexer session are marked
+        app.config.settings.set_multiplexer(Multiplexer::Tmux);
+        app.mux_sessions.insert("workman-my-feature__frontend".to_string());
+        let items = app.get_tree_items();
+        assert!(!items[0].0.contains("[tmux]"));
+        assert!(items[1].0.ends_with("[tmux]"));
+    }
+
+    #[test]
+    fn test_setup_status_and_task_events() {
+        let mut app = make_test_app();
+        let wt_path = PathBuf::from("/frontend/.workman/feat-x");
+        app.config.projects.push(Project {
+            name: "x".to_string(),
+            branch: "feat/x".to_string(),
+            folder: PathBuf::from("/tmp/.workman/projects/x"),
+            worktrees: vec![
+                ProjectWorktree { repo_name: "frontend".to_string(), path: wt_path.clone() },
+            ],
+        });
+        app.expanded_projects.insert(0);
+        app.setting_up.insert(wt_path.clone());
+
+        let items = app.get_tree_items();
+        assert!(items[1].0.contains("setting up"));
+
+        app.task_tx.send(TaskEvent::Output { label: "frontend".to_string(), line: "installing".to_string() }).unwrap();
+        app.task_tx.send(TaskEvent::SetupFinished { path: wt_path.clone(), label: "frontend".to_string(), success: true }).unwrap();
+        drain_task_events(&mut app);
+
+        assert!(!app.setting_up.contains(&wt_path));
+        assert_eq!(app.command_output[0], "[frontend] installing");
+        assert_eq!(app.command_output[1], "[frontend] setup complete");
+        assert!(app.error_message.is_none());
+    }
+
+    #[test]
+    fn test_hooks_running_marks_row_and_project() {
+        let mut app = make_test_app();
+        let worktree = |name: &str| ProjectWorktree {
+            repo_name: name.to_string(),
+            path: PathBuf::from(format!("/wt/{}", name)),
+        };
+        app.config.projects.push(Project {
+            name: "x".to_string(),
+            branch: "feat/x".to_string(),
+            folder: PathBuf::from("/tmp/.workman/projects/x"),
+            worktrees: vec![worktree("api"), worktree("web")],
+        });
+        app.expanded_projects.insert(0);
+
+        // A worktree's pre_push blocks it and its project, not its sibling
+        app.running_hooks.insert(PathBuf::from("/wt/api"));
+        assert!(app.hooks_running(Selection::Worktree(0, 0)));
+        assert!(app.hooks_running(Selection::Project(0)));
+        assert!(!app.hooks_running(Selection::Worktree(0, 1)));
+        assert!(app.get_tree_items()[1].0.contains("running hooks"));
+
+        // A project's hooks block all of its rows
+        app.running_hooks.clear();
+        app.running_hooks.insert(app.config.projects[0].folder.clone());
+        assert!(app.hooks_running(Selection::Worktree(0, 1)));
+        assert!(app.get_tree_items()[0].0.contains("running hooks"));
+    }
+
+    #[test]
+    fn test_user_commands_and_results() {
+        let mut app = make_test_app();
+        let api = tempfile::tempdir().unwrap();
+        std::fs::write(
+            api.path().join(crate::repo_config::REPO_CONFIG_FILE),
+            "[[commands]]\nname = \"test\"\ncommand = \"cargo test\"\n\n[[commands]]\nname = \"migrate\"\ncommand = \"sqlx migrate run\"\n",
+        ).unwrap();
+        app.config.repos.push(Repo { name: "api".to_string(), path: api.path().to_path_buf() });
+        app.config.settings.commands.push(UserCommand { name: "test".to_string(), command: "make test".to_string(), terminal: false });
+        app.config.projects.push(Project {
+            name: "x".to_string(),
+            branch: "feat/x".to_string(),
+            folder: PathBuf::from("/tmp/.workman/projects/x"),
+            worktrees: vec![
+                ProjectWorktree { repo_name: "web".to_string(), path: PathBuf::from("/web/.workman/feat-x") },
+                ProjectWorktree { repo_name: "api".to_string(), path: PathBuf::from("/api/.workman/feat-x") },
+            ],
+        });
+
+        let names = |cmds: Vec<UserCommand>| cmds.into_iter().map(|c| c.command).collect::<Vec<_>>();
+        assert_eq!(names(app.commands_for(Selection::Worktree(0, 0))), vec!["make test"]);
+        assert_eq!(names(app.commands_for(Selection::Worktree(0, 1))), vec!["cargo test", "sqlx migrate run"]);
+        // A project offers every command any of its worktrees has
+        assert_eq!(app.commands_for(Selection::Project(0)).len(), 2);
+        assert!(app.run_user_command(Selection::Worktree(0, 0), "migrate").is_err());
+
+        app.task_tx.send(TaskEvent::CommandFinished {
+            name: "test".to_string(),
+            results: vec![("web".to_string(), true), ("api".to_string(), false)],
+        }).unwrap();
+        drain_task_events(&mut app);
+        assert_eq!(app.command_output.last().unwrap(), "test: 1/2 succeeded");
+        assert_eq!(app.error_message.as_deref(), Some("'test' failed in [api] (see output)"));
+    }
+
+    #[test]
+    fn test_action_matches_follow_selection_and_query() {
+        let mut app = make_test_app();
+        app.config.projects.push(Project {
+            name: "x".to_string(),
+            branch: "feat/x".to_string(),
+            folder: PathBuf::from("/tmp/.workman/projects/x"),
+            worktrees: vec![
+                ProjectWorktree { repo_name: "api".to_string(), path: PathBuf::from("/api/.workman/feat-x") },
+            ],
+        });
+        app.expanded_projects.insert(0);
+
+        app.tree_state.select(Some(0));
+        app.update_action_matches();
+        assert!(app.action_matches.contains(&Action::AddRepo));
+        assert!(!app.action_matches.contains(&Action::Diff));
+        // Nothing to cycle or close yet
+        assert!(!app.action_matches.contains(&Action::NextTab));
+        assert!(!app.action_matches.contains(&Action::ClosePane));
+
+        app.tree_state.select(Some(1));
+        app.input = "diff".to_string();
+        app.update_action_matches();
+        assert_eq!(app.action_matches.first(), Some(&Action::Diff));
+
+        app.input = "push".to_string();
+        app.update_action_matches();
+        assert_eq!(app.action_matches.first(), Some(&Action::Push));
+        assert!(!app.action_matches.contains(&Action::Diff));
+    }
+
+    #[test]
+    fn test_tree_motions() {
+        let mut app = make_test_app();
+        for name in ["a", "b", "c"] {
+            app.config.projects.push(Project {
+                name: name.to_string(),
+                branch: format!("feat/{}", name),
+                folder: PathBuf::from(format!("/tmp/.workman/projects/{}", name)),
+                worktrees: vec![
+                    ProjectWorktree { repo_name: "api".to_string(), path: PathBuf::from(format!("/api/{}", name)) },
+                    ProjectWorktree { repo_name: "web".to_string(), path: PathBuf::from(format!("/web/{}", name)) },
+                ],
+            });
+        }
+        app.expanded_projects.extend([0, 1]);
+        app.tree_state.select(Some(0));
+
+        // a, a/api, a/web, b, b/api, b/web, c
+        app.select_last();
+        assert_eq!(app.get_selected_selection(), Some(Selection::Project(2)));
+        app.move_by(-100);
+        assert_eq!(app.tree_state.selected(), Some(0));
+
+        app.jump_project(true);
+        assert_eq!(app.get_selected_selection(), Some(Selection::Project(1)));
+        app.jump_project(false);
+        app.jump_project(false);
+        assert_eq!(app.get_selected_selection(), Some(Selection::Project(2)));
+
+        // l expands, then steps in; h steps out, then collapses
+        app.expand_selected();
+        assert!(app.expanded_projects.contains(&2));
+        app.expand_selected();
+        assert_eq!(app.get_selected_selection(), Some(Selection::Worktree(2, 0)));
+        app.collapse_selected();
+        assert_eq!(app.get_selected_selection(), Some(Selection::Project(2)));
+        app.collapse_selected();
+        assert!(!app.expanded_projects.contains(&2));
+
+        // Dirty worktrees are found in collapsed projects too, wrapping around
+        app.worktree_status.insert((0, 0), "clean".to_string());
+        app.worktree_status.insert((0, 1), "3/-1".to_string());
+        app.worktree_status.insert((2, 1), "0/-0 ↑1".to_string());
+        app.select_first();
+        app.next_dirty_worktree();
+        assert_eq!(app.get_selected_selection(), Some(Selection::Worktree(0, 1)));
+        app.next_dirty_worktree();
+        assert_eq!(app.get_selected_selection(), Some(Selection::Worktree(2, 1)));
+        app.next_dirty_worktree();
+        assert_eq!(app.get_selected_selection(), Some(Selection::Worktree(0, 1)));
+    }
+
+    #[test]
+    fn test_tree_filter() {
+        let mut app = make_test_app();
+        for name in ["billing", "search"] {
+            app.config.projects.push(Project {
+                name: name.to_string(),
+                branch: format!("feat/{}", name),
+                folder: PathBuf::from(format!("/tmp/.workman/projects/{}", name)),
+                worktrees: vec![
+                    ProjectWorktree { repo_name: "api".to_string(), path: PathBuf::from(format!("/api/{}", name)) },
+                    ProjectWorktree { repo_name: "web".to_string(), path: PathBuf::from(format!("/web/{}", name)) },
+                ],
+            });
+        }
+        app.worktree_status.insert((0, 0), "clean".to_string());
+        app.worktree_status.insert((0, 1), "3/-1".to_string());
+        app.worktree_status.insert((1, 0), "0/-0 ↑2".to_string());
+        app.worktree_status.insert((1, 1), "N/A".to_string());
+        let shown = |app: &App| app.get_tree_items().into_iter().map(|(_, s, _)| s).collect::<Vec<_>>();
+
+        // A project match keeps all its worktrees, shown expanded; indices are unchanged
+        app.tree_state.select(Some(1));
+        app.set_tree_filter(TreeFilter { query: "srch".to_string(), ..TreeFilter::default() });
+        assert_eq!(shown(&app), vec![Selection::Project(1), Selection::Worktree(1, 0), Selection::Worktree(1, 1)]);
+        assert_eq!(app.get_selected_selection(), Some(Selection::Project(1)));
+        // Projects can't be collapsed while filtered
+        assert!(!Action::ToggleExpand.available(&app, Some(Selection::Project(1))));
+        assert!(!Action::Collapse.available(&app, Some(Selection::Project(1))));
+        assert!(Action::Collapse.available(&app, Some(Selection::Worktree(1, 0))));
+        app.collapse_selected();
+        app.toggle_project_expand(1);
+        assert_eq!(shown(&app).len(), 3);
+
+        // A repo name match keeps only that worktree
+        app.set_tree_filter(TreeFilter { query: "web".to_string(), ..TreeFilter::default() });
+        assert_eq!(
+            shown(&app),
+            vec![Selection::Project(0), Selection::Worktree(0, 1), Selection::Project(1), Selection::Worktree(1, 1)]
+        );
+
+        // Status filters hide projects left empty
+        app.select_in_tree(Selection::Worktree(1, 1));
+        app.set_tree_filter(TreeFilter { changes_only: true, ..TreeFilter::default() });
+        assert_eq!(
+            shown(&app),
+            vec![Selection::Project(0), Selection::Worktree(0, 1), Selection::Project(1), Selection::Worktree(1, 0)]
+        );
+        // (1, 1) is hidden, so the cursor falls back to the first row
+        assert_eq!(app.get_selected_selection(), Some(Selection::Project(0)));
+        app.set_tree_filter(TreeFilter { query: "bil".to_string(), changes_only: true, ..TreeFilter::default() });
+        assert_eq!(shown(&app), vec![Selection::Project(0), Selection::Worktree(0, 1)]);
+        app.set_tree_filter(TreeFilter { unpushed_only: true, ..TreeFilter::default() });
+        assert_eq!(shown(&app), vec![Selection::Project(1), Selection::Worktree(1, 0)]);
+        app.tree_state.select(Some(1));
+        // The cursor stays on its row while it is shown
+        app.set_tree_filter(TreeFilter { query: "api".to_string(), unpushed_only: true, ..TreeFilter::default() });
+        assert_eq!(app.get_selected_selection(), Some(Selection::Worktree(1, 0)));
+
+        // Jumping to a hidden row drops the filter
+        app.select_in_tree(Selection::Worktree(0, 0));
+        assert!(!app.tree_filter.is_active());
+        assert_eq!(app.get_selected_selection(), Some(Selection::Worktree(0, 0)));
+
+        // A worktree turning clean drops out of "changes only" without moving the cursor
+        app.set_tree_filter(TreeFilter { changes_only: true, ..TreeFilter::default() });
+        app.select_in_tree(Selection::Worktree(1, 0));
+        let path = app.config.projects[0].worktrees[1].path.clone();
+        app.handle_task_event(TaskEvent::StatusRefreshed(vec![((0, 1), path, "clean".to_string())]));
+        assert_eq!(shown(&app), vec![Selection::Project(1), Selection::Worktree(1, 0)]);
+        assert_eq!(app.get_selected_selection(), Some(Selection::Worktree(1, 0)));
+
+        app.set_tree_filter(TreeFilter { query: "nothing".to_string(), ..TreeFilter::default() });
+        assert!(shown(&app).is_empty());
+        assert_eq!(app.get_selected_selection(), None);
+    }
+
+    #[test]
+    fn test_status_refresh_and_output_notifications() {
+        let mut app = make_test_app();
+        let wt_path = PathBuf::from("/frontend/.workman/feat-x");
+        app.config.projects.push(Project {
+            name: "x".to_string(),
+            branch: "feat/x".to_string(),
+            folder: PathBuf::from("/tmp/.workman/projects/x"),
+            worktrees: vec![
+                ProjectWorktree { repo_name: "frontend".to_string(), path: wt_path.clone() },
+            ],
+        });
+
+        // Results for a worktree that has since moved are dropped
+        app.task_tx.send(TaskEvent::StatusRefreshed(vec![
+            ((0, 0), wt_path.clone(), "+1 -0".to_string()),
+            ((0, 1), PathBuf::from("/gone"), "clean".to_string()),
+        ])).unwrap();
+        drain_task_events(&mut app);
+        assert_eq!(app.worktree_status.get(&(0, 0)).map(String::as_str), Some("+1 -0"));
+        assert!(!app.worktree_status.contains_key(&(0, 1)));
+
+        // Output bursts wake the loop once until it redraws
+        app.output_notifier.notify();
+        app.output_notifier.notify();
+        assert!(matches!(app.task_rx.try_recv(), Ok(TaskEvent::SessionOutput)));
+        assert!(app.task_rx.try_recv().is_err());
+        app.output_notifier.clear();
+        app.output_notifier.notify();
+        assert!(matches!(app.task_rx.try_recv(), Ok(TaskEvent::SessionOutput)));
+    }
+
+    #[tokio::test]
+    async fn test_session_exit_and_rekey_on_removal() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut app = make_test_app();
+        let worktree = |name: &str| ProjectWorktree {
+            repo_name: name.to_string(),
+            path: temp_dir.path().to_path_buf(),
+        };
+        app.config.projects.push(Project {
+            name: "x".to_string(),
+            branch: "feat/x".to_string(),
+            folder: temp_dir.path().to_path_buf(),
+            worktrees: vec![worktree("a"), worktree("b"), worktree("c")],
+        });
+        // An explicit command keeps the test independent of the user's shell and rc files
+        let command = || Some("sh -c 'read _; exit 3'".to_string());
+        for w_idx in 0..3 {
+            app.start_session(Selection::Worktree(0, w_idx), command(), 40, 10).unwrap();
+        }
+        // Tag each session's screen so sessions can be told apart after re-keying
+        for w_idx in 0..3 {
+            let session = app.session(Selection::Worktree(0, w_idx)).unwrap();
+            session.parser.lock().unwrap().process(format!("session-{}", w_idx).as_bytes());
+        }
+
+        app.remove_worktree_session(0, 1);
+        assert_eq!(app.sessions.len(), 2);
+        let contents = app.session(Selection::Worktree(0, 1)).unwrap().parser.lock().unwrap().screen().contents();
+        assert!(contents.contains("session-2"));
+
+        // The process exiting is noticed and reported in the pane
+        let sel = Selection::Worktree(0, 0);
+        app.session_mut(sel).unwrap().write(b"\n").unwrap();
+        let mut code = None;
+        for _ in 0..100 {
+            code = app.session_mut(sel).unwrap().poll_exit();
+            if code.is_some() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+        assert_eq!(code, Some(3));
+        let contents = app.session(sel).unwrap().parser.lock().unwrap().screen().contents();
+        assert!(contents.contains("[process exited with code 3]"));
+
+        app.remove_project_sessions(0);
+        assert!(app.sessions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_session_switcher_lists_in_tree_order() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut app = make_test_app();
+        app.config.projects.push(Project {
+            name: "x".to_string(),
+            branch: "feat/x".to_string(),
+            folder: temp_dir.path().to_path_buf(),
+            worktrees: vec![ProjectWorktree {
+                repo_name: "api".to_string(),
+                path: temp_dir.path().to_path_buf(),
+            }],
+        });
+        let wt = Selection::Worktree(0, 0);
+        app.start_session(wt, None, 40, 10).unwrap();
+        app.start_session(wt, Some("sleep 30".to_string()), 40, 10).unwrap();
+        app.start_session(Selection::Project(0), None, 40, 10).unwrap();
+        app.session_cursor = 5;
+
+        app.refresh_session_entries();
+        let rows: Vec<(Selection, usize)> = app.session_entries.iter().map(|e| (e.sel, e.tab)).collect();
+        assert_eq!(rows, vec![(Selection::Project(0), 0), (wt, 0), (wt, 1)]);
+        assert_eq!(app.session_cursor, 2);
+        assert_eq!(app.session_entries[1].cwd, temp_dir.path());
+
+        assert_eq!(app.session_label(wt, 0), "x / api · shell");
+        assert_eq!(app.session_label(wt, 1), "x / api · sleep 30");
+        app.sessions.get_mut(&wt).unwrap().tabs[1].name = Some("server".to_string());
+        assert_eq!(app.session_label(wt, 1), "x / api · server");
+        assert_eq!(app.session_badge(wt), "  [2 sessions]");
+
+        let tabs = app.sessions.get_mut(&wt).unwrap();
+        tabs.cycle(1);
+        assert_eq!(tabs.active, 0);
+        tabs.cycle(-1);
+        assert_eq!(tabs.active, 1);
+
+        // Closing the active (last) tab falls back to its neighbour; the last one removes the row
+        app.close_session(wt, 1);
+        assert_eq!(app.sessions[&wt].active, 0);
+        app.close_session(wt, 0);
+        assert!(app.session(wt).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_sessions_sized_to_their_pane() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut app = make_test_app();
+        app.config.projects.push(Project {
+            name: "x".to_string(),
+            branch: "feat/x".to_string(),
+            folder: temp_dir.path().to_path_buf(),
+            worktrees: vec![],
+        });
+        app.panel_area = Rect::new(50, 8, 42, 22);
+        app.output_area = app.panel_area;
+        assert_eq!(app.pane_size(), (40, 20));
+
+        let sel = Selection::Project(0);
+        let (width, height) = app.pane_size();
+        app.start_session(sel, None, width, height).unwrap();
+        app.panes.show(sel);
+        app.panes.split(crate::panes::SplitDirection::Vertical);
+        app.resize_panes();
+        // Shown twice, the session takes the focused (bottom) pane's inner size
+        let size = app.session(sel).unwrap().parser.lock().unwrap().screen().size();
+        assert_eq!(size, (9, 40));
     }
 
     #[test]
@@ -0,0 +1,152 @@
+use crate::app::branch_from_name;
+use anyhow::Result;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Template used when none is configured: just the slugified project name.
+pub const DEFAULT_BRANCH_TEMPLATE: &str = "{slug}";
+
+/// How branch names are derived from project names.
+///
+/// ```json
+/// "branch_naming": {
+///   "template": "feat/{ticket}-{slug}",
+///   "ticket_pattern": "[A-Z]+-[0-9]+",
+///   "max_length": 60
+/// }
+/// ```
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct BranchNaming {
+    /// Branch template; `{slug}`, `{ticket}` and `{user}` are substituted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    /// Regex that finds a ticket id in the project name. The first capture group is used
+    /// when present, otherwise the whole match. The ticket is left out of `{slug}`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ticket_pattern: Option<String>,
+    /// Value for `{user}`; defaults to `$USER`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    /// Branches longer than this are cut down, at a word boundary where possible.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_length: Option<usize>,
+}
+
+impl BranchNaming {
+    pub fn template(&self) -> &str {
+        self.template.as_deref().unwrap_or(DEFAULT_BRANCH_TEMPLATE)
+    }
+
+    /// Derives the branch for project `name`, with `prefix` (from a project template) in front.
+    /// Placeholders that come out empty are dropped along with their separators.
+    pub fn branch_for(&self, name: &str, prefix: &str) -> Result<String> {
+        let (ticket, rest) = match &self.ticket_pattern {
+            Some(pattern) => {
+                let re = Regex::new(pattern)
+                    .map_err(|e| anyhow::anyhow!("Invalid ticket_pattern: {}", e))?;
+                match re.captures(name) {
+                    Some(caps) => {
+                        let whole = caps.get(0).expect("group 0 always matches");
+                        let ticket = caps.get(1).unwrap_or(whole).as_str().to_string();
+                        let rest = format!("{} {}", &name[..whole.start()], &name[whole.end()..]);
+                        (ticket, rest)
+                    }
+                    None => (String::new(), name.to_string()),
+                }
+            }
+            None => (String::new(), name.to_string()),
+        };
+        let user = self.user.clone()
+            .or_else(|| std::env::var("USER").ok())
+            .unwrap_or_default();
+
+        let expanded = self.template()
+            .replace("{slug}", &branch_from_name(&rest))
+            .replace("{ticket}", ticket.trim())
+            .replace("{user}", &branch_from_name(&user));
+        let mut branch = tidy(&format!("{}{}", prefix, expanded));
+
+        if let Some(max) = self.max_length
+            && branch.chars().count() > max
+        {
+            let cut: String = branch.chars().take(max).collect();
+            // Prefer ending on a whole word over leaving a dangling fragment
+            let mid_word = branch.chars().nth(max).is_some_and(|c| c != '-' && c != '/');
+            branch = match cut.rfind(['-', '/']) {
+                Some(i) if mid_word && i > 0 => tidy(&cut[..i]),
+                _ => tidy(&cut),
+            };
+        }
+        Ok(branch)
+    }
+}
+
+/// Collapses the debris left by empty placeholders: repeated dashes, empty path segments,
+/// and dashes or dots at the edges of a segment.
+fn tidy(branch: &str) -> String {
+    branch.split('/')
+        .map(|segment| {
+            segment.split('-').filter(|s| !s.is_empty()).collect::<Vec<_>>().join("-")
+                .trim_matches('.').to_string()
+        })
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn naming(template: &str) -> BranchNaming {
+        BranchNaming {
+            template: Some(template.to_string()),
+            ticket_pattern: Some("[A-Z]+-[0-9]+".to_string()),
+            user: Some("Jane Doe".to_string()),
+            max_length: None,
+        }
+    }
+
+    #[test]
+    fn test_default_template_is_slug() {
+        let branch = BranchNaming::default().branch_for("My Feature", "").unwrap();
+        assert_eq!(branch, "my-feature");
+        let branch = BranchNaming::default().branch_for("My Feature", "feat/").unwrap();
+        assert_eq!(branch, "feat/my-feature");
+    }
+
+    #[test]
+    fn test_ticket_and_user_placeholders() {
+        let n = naming("feat/{ticket}-{slug}");
+        assert_eq!(n.branch_for("JIRA-123 Fix login", "").unwrap(), "feat/JIRA-123-fix-login");
+        assert_eq!(n.branch_for("Fix login (JIRA-123)", "").unwrap(), "feat/JIRA-123-fix-login");
+
+        let n = naming("{user}/{slug}");
+        assert_eq!(n.branch_for("Fix login", "").unwrap(), "jane-doe/fix-login");
+    }
+
+    #[test]
+    fn test_missing_ticket_drops_separator() {
+        let n = naming("feat/{ticket}-{slug}");
+        assert_eq!(n.branch_for("Fix login", "").unwrap(), "feat/fix-login");
+        let n = naming("{ticket}/{slug}");
+        assert_eq!(n.branch_for("Fix login", "").unwrap(), "fix-login");
+    }
+
+    #[test]
+    fn test_capture_group_and_max_length() {
+        let n = BranchNaming {
+            template: Some("{ticket}-{slug}".to_string()),
+            ticket_pattern: Some("#([0-9]+)".to_string()),
+            user: None,
+            max_length: Some(12),
+        };
+        assert_eq!(n.branch_for("#42 improve the search page", "").unwrap(), "42-improve");
+    }
+
+    #[test]
+    fn test_invalid_pattern_errors() {
+        let n = BranchNaming { ticket_pattern: Some("(".to_string()), ..Default::default() };
+        assert!(n.branch_for("x", "").is_err());
+    }
+}
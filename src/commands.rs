@@ -0,0 +1,47 @@
+//! User-defined commands (tests, migrations, lint, ...) run from the command palette on a
+//! project or worktree.
+
+use serde::{Deserialize, Serialize};
+
+/// A named shell command. Defined globally (in `Settings`) and per repo (in
+/// `.workman.toml` as `[[commands]]`).
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct UserCommand {
+    pub name: String,
+    pub command: String,
+    /// Run in a new terminal tab instead of capturing its output into the output panel.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub terminal: bool,
+}
+
+/// Global commands followed by the repo's own; a repo command replaces the global one
+/// with the same name.
+pub fn merge(global: &[UserCommand], repo: &[UserCommand]) -> Vec<UserCommand> {
+    let mut merged = global.to_vec();
+    for command in repo {
+        match merged.iter_mut().find(|c| c.name == command.name) {
+            Some(existing) => *existing = command.clone(),
+            None => merged.push(command.clone()),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cmd(name: &str, command: &str) -> UserCommand {
+        UserCommand { name: name.to_string(), command: command.to_string(), terminal: false }
+    }
+
+    #[test]
+    fn test_repo_commands_override_global_by_name() {
+        let global = vec![cmd("test", "make test"), cmd("lint", "make lint")];
+        let repo = vec![cmd("test", "cargo test"), cmd("migrate", "sqlx migrate run")];
+
+        let merged = merge(&global, &repo);
+        assert_eq!(merged, vec![cmd("test", "cargo test"), cmd("lint", "make lint"), cmd("migrate", "sqlx migrate run")]);
+        assert_eq!(merge(&global, &[]), global);
+    }
+}
@@ -0,0 +1,683 @@
+//! Session daemon: a background `workman --session-daemon` process that owns terminal
+//! PTYs so their programs outlive the TUI. workman talks to it over a Unix socket, one
+//! connection per attached session, and reattaches to everything on startup.
+//!
+//! Frames are `[kind: u8][len: u32 BE][payload]`. Requests and replies are JSON; PTY
+//! input and output are raw bytes.
+
+use anyhow::Result;
+use portable_pty::{native_pty_system, CommandBuilder, PtySize};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{self, Read, Write};
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+use std::os::unix::io::AsRawFd;
+use std::net::Shutdown;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
+
+/// Command-line flag that runs the daemon instead of the TUI.
+pub const DAEMON_FLAG: &str = "--session-daemon";
+
+/// Client → daemon: a JSON `Request`.
+const REQUEST: u8 = 1;
+/// Client → daemon: bytes typed into the session.
+const INPUT: u8 = 2;
+/// Daemon → client: a JSON `Reply`.
+const REPLY: u8 = 3;
+/// Daemon → client: bytes the session printed.
+const OUTPUT: u8 = 4;
+
+/// Frames larger than this are treated as a protocol error.
+const MAX_FRAME: usize = 16 * 1024 * 1024;
+
+/// Frames queued for a client before it is considered stalled and disconnected.
+const CLIENT_QUEUE: usize = 1024;
+
+/// How long the daemon waits with no sessions and no clients before exiting.
+const IDLE_GRACE: Duration = Duration::from_millis(500);
+
+#[derive(Serialize, Deserialize, Debug)]
+pub enum Request {
+    /// List the sessions; the connection closes after the reply.
+    List,
+    /// Start a session running `command` (or the shell) in `dir` and attach to it.
+    Spawn { dir: PathBuf, command: Option<String>, cols: u16, rows: u16 },
+    /// Attach to session `id`, resizing it unless a size is 0. The reply is followed
+    /// by the screen contents.
+    Attach { id: u64, cols: u16, rows: u16 },
+    /// On an attached connection: resize the session.
+    Resize { cols: u16, rows: u16 },
+    /// On an attached connection: set or clear the session's name.
+    Rename { name: Option<String> },
+    /// On an attached connection: end the session, killing its process.
+    Kill,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub enum Reply {
+    Sessions(Vec<SessionInfo>),
+    Attached(SessionInfo),
+    /// The attached session's process exited.
+    Exited { code: u32 },
+    Error(String),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SessionInfo {
+    pub id: u64,
+    pub dir: PathBuf,
+    pub command: Option<String>,
+    pub name: Option<String>,
+    /// The shell's pid, for finding what runs in its foreground.
+    pub pid: Option<u32>,
+    pub exit_code: Option<u32>,
+    /// Current screen size.
+    pub cols: u16,
+    pub rows: u16,
+}
+
+pub enum Frame {
+    Reply(Reply),
+    Output(Vec<u8>),
+}
+
+// ── Framing ───────────────────────────────────────────────────────────────
+
+fn write_frame(w: &mut impl Write, kind: u8, payload: &[u8]) -> io::Result<()> {
+    let mut frame = Vec::with_capacity(payload.len() + 5);
+    frame.push(kind);
+    frame.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    frame.extend_from_slice(payload);
+    w.write_all(&frame)
+}
+
+fn read_frame(r: &mut impl Read) -> io::Result<(u8, Vec<u8>)> {
+    let mut header = [0u8; 5];
+    r.read_exact(&mut header)?;
+    let len = u32::from_be_bytes([header[1], header[2], header[3], header[4]]) as usize;
+    if len > MAX_FRAME {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "frame too large"));
+    }
+    let mut payload = vec![0u8; len];
+    r.read_exact(&mut payload)?;
+    Ok((header[0], payload))
+}
+
+fn write_json(w: &mut impl Write, kind: u8, value: &impl Serialize) -> io::Result<()> {
+    write_frame(w, kind, &serde_json::to_vec(value)?)
+}
+
+pub fn send_request(stream: &mut UnixStream, request: &Request) -> io::Result<()> {
+    write_json(stream, REQUEST, request)
+}
+
+pub fn send_input(stream: &mut UnixStream, data: &[u8]) -> io::Result<()> {
+    write_frame(stream, INPUT, data)
+}
+
+/// Reads the next frame the daemon sent.
+pub fn read_reply(stream: &mut UnixStream) -> io::Result<Frame> {
+    let (kind, payload) = read_frame(stream)?;
+    match kind {
+        REPLY => Ok(Frame::Reply(serde_json::from_slice(&payload)?)),
+        OUTPUT => Ok(Frame::Output(payload)),
+        _ => Err(io::Error::new(io::ErrorKind::InvalidData, "unexpected frame")),
+    }
+}
+
+// ── Client ────────────────────────────────────────────────────────────────
+
+/// Where the daemon listens: a private `workman-<uid>` folder in the user's runtime dir,
+/// else in the temp dir.
+pub fn socket_path() -> PathBuf {
+    let uid = unsafe { libc::getuid() };
+    dirs::runtime_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join(format!("workman-{}", uid))
+        .join("daemon.sock")
+}
+
+/// Creates the socket's folder readable only by us, refusing one that another user owns
+/// or could write to (e.g. planted in a shared `/tmp`).
+fn ensure_private_dir(dir: &Path) -> io::Result<()> {
+    match std::fs::DirBuilder::new().mode(0o700).create(dir) {
+        Err(e) if e.kind() != io::ErrorKind::AlreadyExists => return Err(e),
+        _ => {}
+    }
+    let meta = std::fs::symlink_metadata(dir)?;
+    if !meta.is_dir() || meta.uid() != unsafe { libc::getuid() } || meta.mode() & 0o077 != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("{} must be a folder owned by you with mode 0700", dir.display()),
+        ));
+    }
+    Ok(())
+}
+
+/// The uid of the process on the other end of `stream`.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn peer_uid(stream: &UnixStream) -> io::Result<u32> {
+    let mut cred: libc::ucred = unsafe { std::mem::zeroed() };
+    let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+    let ret = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            (&mut cred as *mut libc::ucred).cast(),
+            &mut len,
+        )
+    };
+    if ret != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(cred.uid)
+}
+
+/// The uid of the process on the other end of `stream`.
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn peer_uid(stream: &UnixStream) -> io::Result<u32> {
+    let (mut uid, mut gid) = (0, 0);
+    if unsafe { libc::getpeereid(stream.as_raw_fd(), &mut uid, &mut gid) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(uid)
+}
+
+/// Whether `stream` was opened by a process running as us.
+fn peer_is_us(stream: &UnixStream) -> bool {
+    peer_uid(stream).is_ok_and(|uid| uid == unsafe { libc::getuid() })
+}
+
+/// Connects to a daemon already listening at `path`, checking it runs as us.
+fn connect_existing(path: &Path) -> io::Result<UnixStream> {
+    if let Some(dir) = path.parent() {
+        ensure_private_dir(dir)?;
+    }
+    let stream = UnixStream::connect(path)?;
+    if !peer_is_us(&stream) {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("{} is served by another user", path.display()),
+        ));
+    }
+    Ok(stream)
+}
+
+/// Connects to the daemon, starting it first if it isn't running.
+pub fn connect(path: &Path) -> Result<UnixStream> {
+    match connect_existing(path) {
+        Ok(stream) => return Ok(stream),
+        Err(e) if e.kind() == io::ErrorKind::PermissionDenied => return Err(e.into()),
+        Err(_) => {}
+    }
+    // Own process group, so closing the terminal or Ctrl+C in it doesn't reach the daemon
+    std::os::unix::process::CommandExt::process_group(
+        std::process::Command::new(std::env::current_exe()?)
+            .arg(DAEMON_FLAG)
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null()),
+        0,
+    )
+    .spawn()?;
+    for _ in 0..50 {
+        std::thread::sleep(Duration::from_millis(40));
+        if let Ok(stream) = connect_existing(path) {
+            return Ok(stream);
+        }
+    }
+    Err(anyhow::anyhow!("Session daemon did not start (socket {})", path.display()))
+}
+
+/// Sessions held by the daemon, or none if it isn't running.
+pub fn list(path: &Path) -> Result<Vec<SessionInfo>> {
+    let Ok(mut stream) = connect_existing(path) else { return Ok(Vec::new()) };
+    send_request(&mut stream, &Request::List)?;
+    match read_reply(&mut stream)? {
+        Frame::Reply(Reply::Sessions(sessions)) => Ok(sessions),
+        Frame::Reply(Reply::Error(e)) => Err(anyhow::anyhow!(e)),
+        _ => Err(anyhow::anyhow!("Unexpected reply from session daemon")),
+    }
+}
+
+/// Sends `Spawn` or `Attach` on a fresh connection and waits for the session's details.
+/// The daemon follows up with the screen contents as output.
+pub fn open(path: &Path, request: &Request) -> Result<(UnixStream, SessionInfo)> {
+    let mut stream = connect(path)?;
+    send_request(&mut stream, request)?;
+    match read_reply(&mut stream)? {
+        Frame::Reply(Reply::Attached(info)) => Ok((stream, info)),
+        Frame::Reply(Reply::Error(e)) => Err(anyhow::anyhow!(e)),
+        _ => Err(anyhow::anyhow!("Unexpected reply from session daemon")),
+    }
+}
+
+// ── Server ────────────────────────────────────────────────────────────────
+
+struct DaemonSession {
+    info: SessionInfo,
+    master: Box<dyn portable_pty::MasterPty + Send>,
+    /// Input for the PTY, written by the session's own thread so a program that stops
+    /// reading blocks nothing else.
+    input: mpsc::Sender<Vec<u8>>,
+    child: Box<dyn portable_pty::Child + Send + Sync>,
+    /// Mirrors the screen so a reattaching client can be brought up to date.
+    parser: vt100::Parser,
+    clients: Vec<Client>,
+}
+
+impl DaemonSession {
+    fn info(&self) -> SessionInfo {
+        let (rows, cols) = self.parser.screen().size();
+        SessionInfo { rows, cols, ..self.info.clone() }
+    }
+
+    /// Queues a frame for every attached client, dropping the ones that hung up or fell
+    /// too far behind.
+    fn broadcast(&mut self, kind: u8, payload: &[u8]) {
+        self.clients.retain(|client| client.send(kind, payload.to_vec()));
+    }
+
+    fn resize(&mut self, cols: u16, rows: u16) {
+        if cols == 0 || rows == 0 || self.parser.screen().size() == (rows, cols) {
+            return;
+        }
+        let _ = self.master.resize(PtySize { rows, cols, pixel_width: 0, pixel_height: 0 });
+        self.parser.screen_mut().set_size(rows, cols);
+    }
+}
+
+impl Drop for DaemonSession {
+    fn drop(&mut self) {
+        if self.info.exit_code.is_none() {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+        }
+    }
+}
+
+/// An attached connection. Frames are queued and written by the client's own thread,
+/// so no socket write happens under a lock.
+struct Client {
+    id: u64,
+    queue: mpsc::SyncSender<(u8, Vec<u8>)>,
+    stream: UnixStream,
+}
+
+impl Client {
+    fn new(id: u64, stream: &UnixStream) -> io::Result<Self> {
+        let (queue, frames) = mpsc::sync_channel::<(u8, Vec<u8>)>(CLIENT_QUEUE);
+        let mut writer = stream.try_clone()?;
+        std::thread::spawn(move || {
+            for (kind, payload) in frames {
+                if write_frame(&mut writer, kind, &payload).is_err() {
+                    break;
+                }
+            }
+            let _ = writer.shutdown(Shutdown::Both);
+        });
+        Ok(Client { id, queue, stream: stream.try_clone()? })
+    }
+
+    /// Queues a frame; false (after hanging up on it) if the client is gone or stalled.
+    fn send(&self, kind: u8, payload: Vec<u8>) -> bool {
+        let queued = self.queue.try_send((kind, payload)).is_ok();
+        if !queued {
+            let _ = self.stream.shutdown(Shutdown::Both);
+        }
+        queued
+    }
+}
+
+#[derive(Default)]
+struct State {
+    next_id: u64,
+    next_client: u64,
+    /// Open connections, counted from accept until their handler returns.
+    connections: usize,
+    /// Each session has its own lock; this one is only held to look them up.
+    sessions: BTreeMap<u64, Arc<Mutex<DaemonSession>>>,
+}
+
+impl State {
+    fn session(&self, id: u64) -> Option<Arc<Mutex<DaemonSession>>> {
+        self.sessions.get(&id).cloned()
+    }
+}
+
+type Shared = Arc<Mutex<State>>;
+
+/// Entry point for `workman --session-daemon`.
+pub fn run() -> Result<()> {
+    // Outlive the terminal workman was started from
+    signal_hook::flag::register(signal_hook::consts::SIGHUP, Arc::new(AtomicBool::new(false)))?;
+    let path = socket_path();
+    // Another daemon already owns the socket
+    if connect_existing(&path).is_ok() {
+        return Ok(());
+    }
+    if let Some(dir) = path.parent() {
+        ensure_private_dir(dir)?;
+    }
+    let _ = std::fs::remove_file(&path);
+    // Anyone who can connect can run commands as us, so the socket is never reachable by others
+    let umask = unsafe { libc::umask(0o077) };
+    let listener = UnixListener::bind(&path);
+    unsafe { libc::umask(umask) };
+    let listener = listener?;
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+    serve(listener, true)
+}
+
+/// Accepts connections from our own user forever. With `exit_when_idle`, the process
+/// exits once the last session is gone and no client is connected.
+pub fn serve(listener: UnixListener, exit_when_idle: bool) -> Result<()> {
+    let state: Shared = Arc::default();
+    let socket = listener.local_addr()?.as_pathname().map(Path::to_path_buf);
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else { continue };
+        if !peer_is_us(&stream) {
+            continue;
+        }
+        state.lock().unwrap().connections += 1;
+        let state = state.clone();
+        let socket = socket.clone();
+        std::thread::spawn(move || {
+            let _ = handle_client(stream, &state);
+            state.lock().unwrap().connections -= 1;
+            if exit_when_idle {
+                exit_if_idle(&state, socket.as_deref());
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Exits if, after a grace period, there are no sessions and no open connections.
+/// Connections are counted under the state lock before they are handled, so a client
+/// still starting its session (or just accepted) keeps the daemon alive.
+fn exit_if_idle(state: &Shared, socket: Option<&Path>) {
+    std::thread::sleep(IDLE_GRACE);
+    let state = state.lock().unwrap();
+    if state.connections == 0 && state.sessions.is_empty() {
+        if let Some(socket) = socket {
+            let _ = std::fs::remove_file(socket);
+        }
+        // Exit holding the lock, so no new connection gets half-served
+        std::process::exit(0);
+    }
+}
+
+fn handle_client(mut stream: UnixStream, state: &Shared) -> Result<()> {
+    let (kind, payload) = read_frame(&mut stream)?;
+    if kind != REQUEST {
+        return Ok(());
+    }
+    let (id, cols, rows) = match serde_json::from_slice(&payload)? {
+        Request::List => {
+            let sessions: Vec<_> = state.lock().unwrap().sessions.values().cloned().collect();
+            let sessions = sessions.iter().map(|session| session.lock().unwrap().info()).collect();
+            write_json(&mut stream, REPLY, &Reply::Sessions(sessions))?;
+            return Ok(());
+        }
+        Request::Spawn { dir, command, cols, rows } => match spawn(state, dir, command, cols, rows) {
+            Ok(id) => (id, cols, rows),
+            Err(e) => {
+                write_json(&mut stream, REPLY, &Reply::Error(format!("Failed to start session: {}", e)))?;
+                return Ok(());
+            }
+        },
+        Request::Attach { id, cols, rows } => (id, cols, rows),
+        _ => return Ok(()),
+    };
+
+    let found = {
+        let mut state = state.lock().unwrap();
+        state.next_client += 1;
+        state.session(id).map(|session| (session, state.next_client))
+    };
+    let Some((session, client_id)) = found else {
+        write_json(&mut stream, REPLY, &Reply::Error(format!("No session {}", id)))?;
+        return Ok(());
+    };
+    // Queue the screen and register under the session's lock, so no output falls between
+    let client = Client::new(client_id, &stream)?;
+    {
+        let mut session = session.lock().unwrap();
+        session.resize(cols, rows);
+        client.send(REPLY, serde_json::to_vec(&Reply::Attached(session.info()))?);
+        client.send(OUTPUT, session.parser.screen().state_formatted());
+        if let Some(code) = session.info.exit_code {
+            client.send(REPLY, serde_json::to_vec(&Reply::Exited { code })?);
+        }
+        session.clients.push(client);
+    }
+    // A killed session must be dropped, so hold it only while handling a frame
+    drop(session);
+
+    let result = serve_attached(&mut stream, state, id);
+    if let Some(session) = state.lock().unwrap().session(id) {
+        session.lock().unwrap().clients.retain(|c| c.id != client_id);
+    }
+    result
+}
+
+/// Handles input and requests from a client attached to session `id` until it hangs up.
+fn serve_attached(stream: &mut UnixStream, state: &Shared, id: u64) -> Result<()> {
+    loop {
+        let (kind, payload) = match read_frame(stream) {
+            Ok(frame) => frame,
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        if kind == INPUT {
+            if let Some(session) = state.lock().unwrap().session(id) {
+                let _ = session.lock().unwrap().input.send(payload);
+            }
+            continue;
+        }
+        let request = serde_json::from_slice(&payload)?;
+        if let Request::Kill = request {
+            let session = state.lock().unwrap().sessions.remove(&id);
+            drop(session);
+            return Ok(());
+        }
+        let Some(session) = state.lock().unwrap().session(id) else { continue };
+        let mut session = session.lock().unwrap();
+        match request {
+            Request::Resize { cols, rows } => session.resize(cols, rows),
+            Request::Rename { name } => session.info.name = name,
+            _ => {}
+        }
+    }
+}
+
+fn spawn(state: &Shared, dir: PathBuf, command: Option<String>, cols: u16, rows: u16) -> Result<u64> {
+    let pair = native_pty_system().openpty(PtySize { rows, cols, pixel_width: 0, pixel_height: 0 })?;
+    let shell = std::env::var("SHELL").unwrap_or_else(|_| "sh".to_string());
+    let mut cmd = CommandBuilder::new(shell);
+    if let Some(command) = &command {
+        cmd.args(["-c", command]);
+    }
+    cmd.cwd(&dir);
+    let child = pair.slave.spawn_command(cmd)?;
+    let mut reader = pair.master.try_clone_reader()?;
+    let mut writer = pair.master.take_writer()?;
+
+    // Ends when the session is dropped, or the PTY goes away
+    let (input, typed) = mpsc::channel::<Vec<u8>>();
+    std::thread::spawn(move || {
+        for data in typed {
+            if writer.write_all(&data).and_then(|()| writer.flush()).is_err() {
+                break;
+            }
+        }
+    });
+
+    let (id, session) = {
+        let mut state = state.lock().unwrap();
+        state.next_id += 1;
+        let id = state.next_id;
+        let info = SessionInfo { id, dir, command, name: None, pid: child.process_id(), exit_code: None, cols, rows };
+        let session = Arc::new(Mutex::new(DaemonSession {
+            info,
+            master: pair.master,
+            input,
+            child,
+            parser: vt100::Parser::new(rows, cols, 1000),
+            clients: Vec::new(),
+        }));
+        state.sessions.insert(id, session.clone());
+        (id, Arc::downgrade(&session))
+    };
+
+    // Holds the session weakly, so killing it isn't held up by this thread
+    std::thread::spawn(move || {
+        let mut buf = [0u8; 4096];
+        while let Ok(n @ 1..) = reader.read(&mut buf) {
+            let Some(session) = session.upgrade() else { return };
+            let mut session = session.lock().unwrap();
+            session.parser.process(&buf[..n]);
+            session.broadcast(OUTPUT, &buf[..n]);
+        }
+        // The PTY hung up: wait for the process to be reapable, then report it
+        loop {
+            let Some(session) = session.upgrade() else { return };
+            let mut session = session.lock().unwrap();
+            if let Ok(Some(status)) = session.child.try_wait() {
+                let code = status.exit_code();
+                session.info.exit_code = Some(code);
+                let notice = format!("\r\n\x1b[0m[process exited with code {}]\r\n", code);
+                session.parser.process(notice.as_bytes());
+                session.broadcast(OUTPUT, notice.as_bytes());
+                if let Ok(reply) = serde_json::to_vec(&Reply::Exited { code }) {
+                    session.broadcast(REPLY, &reply);
+                }
+                return;
+            }
+            drop(session);
+            std::thread::sleep(Duration::from_millis(50));
+        }
+    });
+    Ok(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_roundtrip() {
+        let mut buf = Vec::new();
+        write_json(&mut buf, REQUEST, &Request::Resize { cols: 80, rows: 24 }).unwrap();
+        write_frame(&mut buf, INPUT, b"ls\r").unwrap();
+        let mut r = buf.as_slice();
+        let (kind, payload) = read_frame(&mut r).unwrap();
+        assert_eq!(kind, REQUEST);
+        assert!(matches!(serde_json::from_slice(&payload).unwrap(), Request::Resize { cols: 80, rows: 24 }));
+        assert_eq!(read_frame(&mut r).unwrap(), (INPUT, b"ls\r".to_vec()));
+        assert!(read_frame(&mut r).is_err());
+    }
+
+    #[test]
+    fn test_private_dir_rejects_shared_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        let private = dir.path().join("workman");
+        ensure_private_dir(&private).unwrap();
+        assert_eq!(std::fs::metadata(&private).unwrap().mode() & 0o777, 0o700);
+        // Reusing our own folder is fine; one others can write to is not
+        ensure_private_dir(&private).unwrap();
+        std::fs::set_permissions(&private, std::fs::Permissions::from_mode(0o777)).unwrap();
+        assert!(ensure_private_dir(&private).is_err());
+        let link = dir.path().join("link");
+        std::os::unix::fs::symlink(dir.path(), &link).unwrap();
+        assert!(ensure_private_dir(&link).is_err());
+    }
+
+    /// Waits for output containing `needle`, returning everything read.
+    fn read_until(stream: &mut UnixStream, needle: &str) -> String {
+        let mut seen = String::new();
+        while !seen.contains(needle) {
+            match read_reply(stream).unwrap() {
+                Frame::Output(data) => seen.push_str(&String::from_utf8_lossy(&data)),
+                Frame::Reply(_) => {}
+            }
+        }
+        seen
+    }
+
+    #[test]
+    fn test_session_outlives_client_and_replays_screen() {
+        let dir = tempfile::tempdir().unwrap();
+        let private = dir.path().join("run");
+        ensure_private_dir(&private).unwrap();
+        let path = private.join("d.sock");
+        let listener = UnixListener::bind(&path).unwrap();
+        std::thread::spawn(move || serve(listener, false));
+
+        // `cat` echoes what the clients type
+        let command = Some("echo persisted-$((40+2)); cat".to_string());
+        let spawn = Request::Spawn { dir: dir.path().to_path_buf(), command, cols: 40, rows: 10 };
+        let (mut first, info) = open(&path, &spawn).unwrap();
+        first.set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+        read_until(&mut first, "persisted-42");
+        drop(first);
+
+        // A new client finds the session and gets its screen back
+        let sessions = list(&path).unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].dir, dir.path());
+        assert_eq!((sessions[0].cols, sessions[0].rows), (40, 10));
+        let (mut second, _) = open(&path, &Request::Attach { id: info.id, cols: 0, rows: 0 }).unwrap();
+        second.set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+        read_until(&mut second, "persisted-42");
+        send_input(&mut second, b"typed\n").unwrap();
+        read_until(&mut second, "typed");
+
+        send_request(&mut second, &Request::Kill).unwrap();
+        for _ in 0..100 {
+            if list(&path).unwrap().is_empty() {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        panic!("session was not killed");
+    }
+
+    #[test]
+    fn test_stuck_session_blocks_no_other() {
+        let dir = tempfile::tempdir().unwrap();
+        let private = dir.path().join("run");
+        ensure_private_dir(&private).unwrap();
+        let path = private.join("d.sock");
+        let listener = UnixListener::bind(&path).unwrap();
+        std::thread::spawn(move || serve(listener, false));
+        let spawn = |command: &str| Request::Spawn {
+            dir: dir.path().to_path_buf(),
+            command: Some(command.to_string()),
+            cols: 40,
+            rows: 10,
+        };
+
+        // Never reads its input, so the PTY's input buffer fills up and writes to it block
+        let (mut stuck, _) = open(&path, &spawn("stty raw -echo; sleep 30")).unwrap();
+        std::thread::spawn(move || send_input(&mut stuck, &vec![b'x'; 1 << 20]));
+        std::thread::sleep(Duration::from_millis(200));
+
+        let (done, finished) = mpsc::channel();
+        let (path, cat) = (path.clone(), spawn("cat"));
+        std::thread::spawn(move || {
+            let (mut other, _) = open(&path, &cat).unwrap();
+            other.set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+            send_input(&mut other, b"still-alive\n").unwrap();
+            read_until(&mut other, "still-alive");
+            let _ = done.send(());
+        });
+        finished.recv_timeout(Duration::from_secs(10)).expect("a stuck session blocked the daemon");
+    }
+}
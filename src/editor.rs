@@ -0,0 +1,81 @@
+//! Opening a project folder or worktree in the user's editor.
+
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Editors that open their own window; anything else is assumed to run in the terminal.
+const GUI_EDITORS: &[&str] = &[
+    "code", "code-insiders", "codium", "cursor", "windsurf", "zed", "subl", "fleet",
+    "idea", "pycharm", "goland", "clion", "webstorm", "rustrover", "gvim", "mvim",
+    "gedit", "kate", "mate", "open", "xdg-open",
+];
+
+/// The editor command: the configured one, else `$VISUAL`, else `$EDITOR`.
+pub fn resolve(configured: Option<&str>) -> Option<String> {
+    configured.map(str::to_string)
+        .or_else(|| std::env::var("VISUAL").ok())
+        .or_else(|| std::env::var("EDITOR").ok())
+        .map(|c| c.trim().to_string())
+        .filter(|c| !c.is_empty())
+}
+
+/// Whether `command` opens a window of its own, so it can be started in the background.
+/// `override_gui` (the `editor_gui` setting) wins over the guess from the program name.
+pub fn is_gui(command: &str, override_gui: Option<bool>) -> bool {
+    override_gui.unwrap_or_else(|| {
+        let program = command.split_whitespace().next().unwrap_or_default();
+        let name = program.rsplit('/').next().unwrap_or_default();
+        GUI_EDITORS.contains(&name)
+    })
+}
+
+/// Shell command line opening `path`: `{path}` in `command` is replaced by it (quoted),
+/// otherwise it is appended.
+pub fn command_line(command: &str, path: &Path) -> String {
+    let quoted = format!("'{}'", path.to_string_lossy().replace('\'', r"'\''"));
+    if command.contains("{path}") {
+        command.replace("{path}", &quoted)
+    } else {
+        format!("{} {}", command, quoted)
+    }
+}
+
+/// `command` opening `path`, run by `$SHELL -c` in `path`.
+pub fn build(command: &str, path: &Path) -> Command {
+    let shell = std::env::var("SHELL").unwrap_or_else(|_| "sh".to_string());
+    let mut cmd = Command::new(shell);
+    cmd.arg("-c").arg(command_line(command, path)).current_dir(path);
+    cmd
+}
+
+/// Starts a GUI editor without tying it to workman's terminal, so it outlives workman.
+pub fn spawn_detached(mut cmd: Command) -> std::io::Result<()> {
+    let mut child = std::os::unix::process::CommandExt::process_group(
+        cmd.stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null()),
+        0,
+    )
+    .spawn()?;
+    // Reap it when it exits (launchers like `code` return right away)
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_line_and_gui_guess() {
+        let path = Path::new("/work/it's here");
+        assert_eq!(command_line("nvim", path), r"nvim '/work/it'\''s here'");
+        assert_eq!(command_line("code -n {path} --wait", path), r"code -n '/work/it'\''s here' --wait");
+
+        assert!(is_gui("code -n", None));
+        assert!(is_gui("/usr/local/bin/zed", None));
+        assert!(!is_gui("nvim", None));
+        assert!(is_gui("nvim", Some(true)));
+        assert!(!is_gui("code --wait", Some(false)));
+
+        assert_eq!(resolve(Some("  hx ")), Some("hx".to_string()));
+    }
+}
@@ -1,215 +1,126 @@
 use crate::terminal_handler;
 use anyhow::Result;
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
-use std::{fs, path::PathBuf};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use std::{fs, path::{Path, PathBuf}};
 
-use crate::app::{branch_from_name, App, InputMode, Selection};
-use crate::models::{Config, Project, ProjectWorktree, Repo};
-use crate::session::Session;
+use crate::actions::Action;
+use crate::app::{App, InputMode, OptionItem, Selection, TreeFilter};
+use crate::branch_naming::DEFAULT_BRANCH_TEMPLATE;
+use crate::editor;
+use crate::hooks::{self, AfterHooks, HookContext, HookPoint};
+use crate::keys::KeyChord;
+use crate::panes::{FocusMove, SplitDirection};
+use crate::models::{
+    Config, Project, ProjectTemplate, ProjectWorktree, Repo, DEFAULT_DETACH_KEY, DEFAULT_WORKTREE_LOCATION,
+    validate_worktree_location,
+};
+use crate::repo_config::{RepoConfig, REPO_CONFIG_FILE};
+use crate::tasks::{self, HookJob, TaskEvent};
 
 pub enum AppState {
     Continue,
     Quit,
-    /// Suspend workman, run a tmux session, then resume.
-    TmuxSession { path: PathBuf, session_name: String },
+    /// Suspend workman, run a program that takes over the terminal (a multiplexer
+    /// session, a terminal editor), then resume.
+    Suspend(std::process::Command),
 }
 
 pub async fn handle_key_event(
     key: KeyEvent,
     app: &mut App,
-    current_width: u16,
-    current_height: u16,
 ) -> Result<AppState> {
     // Global Ctrl+C
-    if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
-        if app.input_mode != InputMode::Terminal {
-            return Ok(AppState::Quit);
-        }
+    if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c')
+        && app.input_mode != InputMode::Terminal
+    {
+        return Ok(AppState::Quit);
     }
 
-    // Global Ctrl+L: export log
-    if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('l') {
-        if let Some(detail) = &app.full_error_detail {
-            let _ = fs::write("/tmp/workman.log", detail);
-            app.error_message = Some("Log exported to /tmp/workman.log".to_string());
-        } else if let Some(err) = &app.error_message {
-            let _ = fs::write("/tmp/workman.log", err);
-            app.error_message = Some("Status exported to /tmp/workman.log".to_string());
-        }
+    // Any key press ends a mouse selection and its "copied" notice
+    app.mouse_selection = None;
+    app.terminal_warning = None;
+
+    // Export log (Ctrl+L): everywhere but the terminal, as long as it is bound to a Ctrl
+    // chord that can't be typed into a prompt
+    if app.input_mode != InputMode::Terminal
+        && app.keymap.key(Action::ExportLog)
+            .is_some_and(|k| k.modifiers.contains(KeyModifiers::CONTROL) && k.matches(&key))
+    {
+        export_log(app);
         return Ok(AppState::Continue);
     }
 
+    // Alt+arrows move focus between split panes, attached or not
+    if matches!(app.input_mode, InputMode::Normal | InputMode::Terminal)
+        && key.modifiers.contains(KeyModifiers::ALT)
+        && app.panes.len() > 1
+    {
+        let dir = match key.code {
+            KeyCode::Left => Some(FocusMove::Left),
+            KeyCode::Right => Some(FocusMove::Right),
+            KeyCode::Up => Some(FocusMove::Up),
+            KeyCode::Down => Some(FocusMove::Down),
+            _ => None,
+        };
+        if let Some(dir) = dir {
+            if let Some(idx) = app.panes.neighbour(app.panel_area, dir) {
+                app.focus_pane(idx);
+            }
+            return Ok(AppState::Continue);
+        }
+    }
+
     match app.input_mode {
         // ── Normal mode ──────────────────────────────────────────────────
-        InputMode::Normal => match key.code {
-            KeyCode::Char('q') => return Ok(AppState::Quit),
-
-            // New project
-            KeyCode::Char('n') => {
-                app.input_mode = InputMode::AddingProjectName;
-                app.input.clear();
-                app.pending_project_name.clear();
-                app.error_message = None;
-                app.full_error_detail = None;
+        InputMode::Normal => {
+            if let Some(action) = app.keymap.action_for(&key) {
+                return Ok(perform(app, action));
             }
-
-            // Add a repo to the selected project (opens fuzzy path picker)
-            KeyCode::Char('a') => {
-                if let Some(Selection::Project(p_idx)) = app.get_selected_selection() {
-                    app.adding_to_project = Some(p_idx);
-                    app.fuzzy_cursor = None;
-                    app.input.clear();
-                    app.update_fuzzy_results();
-                    app.input_mode = InputMode::AddingRepo;
+            match key.code {
+                KeyCode::Down => app.next(),
+                KeyCode::Up => app.previous(),
+                KeyCode::Esc => {
+                    // Clears messages first, then the tree filter
+                    if app.error_message.is_none() && app.command_output.is_empty() && app.tree_filter.is_active() {
+                        app.set_tree_filter(TreeFilter::default());
+                    }
                     app.error_message = None;
                     app.full_error_detail = None;
-                } else {
-                    app.error_message = Some("Select a project first.".to_string());
-                }
-            }
-
-            // Remove project or worktree — requires confirmation
-            KeyCode::Char('x') => {
-                match app.get_selected_selection() {
-                    Some(sel @ Selection::Project(_)) | Some(sel @ Selection::Worktree(_, _)) => {
-                        app.pending_delete = Some(sel);
-                        app.input_mode = InputMode::ConfirmDelete;
-                    }
-                    _ => {}
-                }
-            }
-
-            // Open terminal (worktree or project level)
-            KeyCode::Char('t') => {
-                match app.get_selected_selection() {
-                    Some(sel @ Selection::Worktree(p_idx, w_idx)) => {
-                        let wt_path = app.config.projects[p_idx].worktrees[w_idx].path.clone();
-                        let repo_name = app.config.projects[p_idx].worktrees[w_idx].repo_name.clone();
-                        let project_name = app.config.projects[p_idx].name.clone();
-
-                        if app.config.settings.use_tmux {
-                            let session_name = sanitize_tmux_name(&format!(
-                                "workman-{}-{}", project_name, repo_name
-                            ));
-                            return Ok(AppState::TmuxSession { path: wt_path, session_name });
-                        }
-
-                        if !app.sessions.contains_key(&sel) {
-                            match Session::new(wt_path, current_width, current_height) {
-                                Ok(session) => { app.sessions.insert(sel, session); }
-                                Err(e) => {
-                                    app.error_message = Some(format!("Failed to start session: {}", e));
-                                    return Ok(AppState::Continue);
-                                }
-                            }
-                        }
-                        app.input_mode = InputMode::Terminal;
-                    }
-                    Some(sel @ Selection::Project(p_idx)) => {
-                        let folder = app.config.projects[p_idx].folder.clone();
-                        let project_name = app.config.projects[p_idx].name.clone();
-
-                        if app.config.settings.use_tmux {
-                            let session_name = sanitize_tmux_name(&format!("workman-{}", project_name));
-                            return Ok(AppState::TmuxSession { path: folder, session_name });
-                        }
-
-                        if !app.sessions.contains_key(&sel) {
-                            match Session::new(folder, current_width, current_height) {
-                                Ok(session) => { app.sessions.insert(sel, session); }
-                                Err(e) => {
-                                    app.error_message = Some(format!("Failed to start session: {}", e));
-                                    return Ok(AppState::Continue);
-                                }
-                            }
-                        }
-                        app.input_mode = InputMode::Terminal;
-                    }
-                    _ => {}
+                    app.command_output.clear();
                 }
+                _ => {}
             }
+        }
 
-            // Push: single worktree or all worktrees in project
-            KeyCode::Char('p') => {
-                match app.get_selected_selection() {
-                    Some(Selection::Worktree(_, _)) | Some(Selection::Project(_)) => {
-                        app.input_mode = InputMode::EditingCommitMessage;
-                        app.input.clear();
-                        app.error_message = None;
-                        app.full_error_detail = None;
-                    }
-                    _ => {}
-                }
-            }
-
-            // Diff (worktree only)
-            KeyCode::Char('d') => {
-                if let Some(sel @ Selection::Worktree(p_idx, w_idx)) = app.get_selected_selection() {
-                    match app.config.projects[p_idx].worktrees[w_idx].get_diff() {
-                        Ok(out) => {
-                            let mut full_output = Vec::new();
-                            full_output.extend_from_slice(&out.stdout);
-                            full_output.extend_from_slice(&out.stderr);
-
-                            if let Some(session) = app.sessions.get(&sel) {
-                                session.parser.lock().unwrap().process(&full_output);
-                                app.input_mode = InputMode::ViewingDiff;
-                            } else {
-                                app.command_output = String::from_utf8_lossy(&full_output)
-                                    .lines().map(String::from).collect();
-                                if !out.status.success() {
-                                    app.error_message = Some("Failed to get diff".to_string());
-                                    app.full_error_detail = Some(app.command_output.join("\n"));
-                                } else if app.command_output.is_empty() {
-                                    app.error_message = Some("No changes to diff.".to_string());
-                                } else {
-                                    app.input_mode = InputMode::ViewingDiff;
-                                    app.error_message = None;
-                                    app.full_error_detail = None;
-                                    app.diff_scroll_offset = 0;
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            app.error_message = Some("System error getting diff".to_string());
-                            app.full_error_detail = Some(e.to_string());
-                        }
-                    }
-                }
-            }
-
-            // Options overlay
-            KeyCode::Char('o') => {
-                app.input_mode = InputMode::Options;
-                app.options_cursor = 0;
-                app.error_message = None;
-            }
-
-            // Help view
-            KeyCode::Char('h') => {
-                app.input_mode = InputMode::Help;
-                app.error_message = None;
-            }
-
-            // Expand/collapse project
+        // ── Tree filter query ─────────────────────────────────────────────
+        InputMode::Filtering => match key.code {
             KeyCode::Enter => {
-                if let Some(Selection::Project(p_idx)) = app.get_selected_selection() {
-                    app.toggle_project_expand(p_idx);
-                }
+                app.input.clear();
+                app.input_mode = InputMode::Normal;
+            }
+            KeyCode::Esc => {
+                app.input.clear();
+                let filter = TreeFilter { query: String::new(), ..app.tree_filter.clone() };
+                app.set_tree_filter(filter);
+                app.input_mode = InputMode::Normal;
             }
-
             KeyCode::Down => app.next(),
             KeyCode::Up => app.previous(),
-            KeyCode::Esc => {
-                app.error_message = None;
-                app.full_error_detail = None;
-                app.command_output.clear();
+            KeyCode::Char(c) => {
+                app.input.push(c);
+                set_filter_query(app);
+            }
+            KeyCode::Backspace => {
+                app.input.pop();
+                set_filter_query(app);
             }
             _ => {}
         },
 
         // ── Terminal mode ─────────────────────────────────────────────────
         InputMode::Terminal => terminal_handler::handle_terminal_key_event(key, app),
+        InputMode::Scrollback => terminal_handler::handle_scrollback_key_event(key, app),
+        InputMode::ScrollbackSearch => terminal_handler::handle_scrollback_search_key_event(key, app),
 
         // ── Viewing diff ──────────────────────────────────────────────────
         InputMode::ViewingDiff => match key.code {
@@ -236,27 +147,193 @@ pub async fn handle_key_event(
             KeyCode::Esc => {
                 app.input_mode = InputMode::Normal;
             }
+            KeyCode::Up if app.options_cursor > 0 => {
+                app.options_cursor -= 1;
+            }
+            KeyCode::Down if app.options_cursor + 1 < OptionItem::ALL.len() => {
+                app.options_cursor += 1;
+            }
+            KeyCode::Char(' ') | KeyCode::Enter => match OptionItem::ALL[app.options_cursor] {
+                OptionItem::Multiplexer => {
+                    let next = app.config.settings.multiplexer().next();
+                    app.config.settings.set_multiplexer(next);
+                    app.mux_sessions.clear();
+                    app.save_config();
+                    app.spawn_status_refresh();
+                }
+                OptionItem::PersistentSessions => {
+                    app.config.settings.persistent_sessions = !app.config.settings.persistent_sessions;
+                    app.save_config();
+                }
+                OptionItem::WorktreeLocation => {
+                    app.input = app.config.settings.worktree_location().to_string();
+                    app.error_message = None;
+                    app.input_mode = InputMode::EditingOption;
+                }
+                OptionItem::DetachKey => {
+                    app.input = app.config.settings.detach_key().to_string().to_lowercase();
+                    app.error_message = None;
+                    app.input_mode = InputMode::EditingOption;
+                }
+                OptionItem::BranchTemplate => {
+                    app.input = app.config.settings.branch_naming.template().to_string();
+                    app.error_message = None;
+                    app.input_mode = InputMode::EditingOption;
+                }
+                OptionItem::Editor => {
+                    app.input = app.config.settings.editor.clone().unwrap_or_default();
+                    app.error_message = None;
+                    app.input_mode = InputMode::EditingOption;
+                }
+            },
+            _ => {}
+        },
+
+        // ── Session switcher ──────────────────────────────────────────────
+        InputMode::Sessions => match key.code {
+            KeyCode::Up | KeyCode::Char('k') => {
+                app.session_cursor = app.session_cursor.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') if app.session_cursor + 1 < app.session_entries.len() => {
+                app.session_cursor += 1;
+            }
+            KeyCode::Enter => jump_to_session(app, app.session_cursor),
+            KeyCode::Char(c @ '1'..='9') => jump_to_session(app, c as usize - '1' as usize),
+            KeyCode::Char('x') => {
+                if let Some(entry) = app.session_entries.get(app.session_cursor) {
+                    app.close_session(entry.sel, entry.tab);
+                    app.refresh_session_entries();
+                }
+            }
+            KeyCode::Char('r') => {
+                if let Some(entry) = app.session_entries.get(app.session_cursor) {
+                    app.input = app.sessions.get(&entry.sel)
+                        .and_then(|tabs| tabs.tabs.get(entry.tab))
+                        .and_then(|s| s.name.clone())
+                        .unwrap_or_default();
+                    app.input_mode = InputMode::RenamingSession;
+                }
+            }
+            KeyCode::Esc | KeyCode::Char('s') | KeyCode::Char('q') => {
+                app.input_mode = InputMode::Normal;
+            }
+            _ => {}
+        },
+
+        // ── Command palette ───────────────────────────────────────────────
+        InputMode::Commands => match key.code {
+            KeyCode::Up | KeyCode::Char('k') => {
+                app.palette_cursor = app.palette_cursor.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') if app.palette_cursor + 1 < app.palette_commands.len() => {
+                app.palette_cursor += 1;
+            }
+            KeyCode::Enter => run_palette_command(app, app.palette_cursor),
+            KeyCode::Char(c @ '1'..='9') => run_palette_command(app, c as usize - '1' as usize),
+            KeyCode::Esc | KeyCode::Char('r') | KeyCode::Char('q') => {
+                app.input_mode = InputMode::Normal;
+            }
+            _ => {}
+        },
+
+        // ── Action palette ────────────────────────────────────────────────
+        InputMode::Actions => match key.code {
             KeyCode::Up => {
-                if app.options_cursor > 0 {
-                    app.options_cursor -= 1;
+                app.action_cursor = app.action_cursor.saturating_sub(1);
+            }
+            KeyCode::Down if app.action_cursor + 1 < app.action_matches.len() => {
+                app.action_cursor += 1;
+            }
+            KeyCode::Enter => {
+                let action = app.action_matches.get(app.action_cursor).copied();
+                app.input.clear();
+                app.input_mode = InputMode::Normal;
+                if let Some(action) = action {
+                    return Ok(perform(app, action));
                 }
             }
-            KeyCode::Down => {
-                // Extend upper bound as more settings are added
-                let max_idx = 0usize; // currently 1 option
-                if app.options_cursor < max_idx {
-                    app.options_cursor += 1;
+            KeyCode::Char(c) => {
+                app.input.push(c);
+                app.action_cursor = 0;
+                app.update_action_matches();
+            }
+            KeyCode::Backspace => {
+                app.input.pop();
+                app.action_cursor = 0;
+                app.update_action_matches();
+            }
+            KeyCode::Esc => {
+                app.input.clear();
+                app.input_mode = InputMode::Normal;
+            }
+            _ => {}
+        },
+
+        InputMode::RenamingSession => match key.code {
+            KeyCode::Enter => {
+                let name = app.input.trim().to_string();
+                if let Some(entry) = app.session_entries.get(app.session_cursor)
+                    && let Some(session) = app.sessions.get_mut(&entry.sel).and_then(|tabs| tabs.tabs.get_mut(entry.tab))
+                {
+                    session.set_name((!name.is_empty()).then_some(name));
+                }
+                app.input.clear();
+                app.input_mode = InputMode::Sessions;
+            }
+            KeyCode::Char(c) => app.input.push(c),
+            KeyCode::Backspace => { app.input.pop(); }
+            KeyCode::Esc => {
+                app.input.clear();
+                app.input_mode = InputMode::Sessions;
+            }
+            _ => {}
+        },
+
+        // ── New tab command prompt ────────────────────────────────────────
+        InputMode::NewTabCommand => match key.code {
+            KeyCode::Enter => {
+                let command = app.input.trim().to_string();
+                app.input.clear();
+                app.input_mode = InputMode::Normal;
+                if let Some(sel) = app.get_selected_selection() {
+                    let command = (!command.is_empty()).then_some(command);
+                    let (width, height) = app.pane_size();
+                    match app.start_session(sel, command, width, height) {
+                        Ok(()) => app.input_mode = InputMode::Terminal,
+                        Err(e) => app.error_message = Some(format!("Failed to start session: {}", e)),
+                    }
                 }
             }
-            KeyCode::Char(' ') | KeyCode::Enter => {
-                match app.options_cursor {
-                    0 => {
-                        app.config.settings.use_tmux = !app.config.settings.use_tmux;
+            KeyCode::Char(c) => app.input.push(c),
+            KeyCode::Backspace => { app.input.pop(); }
+            KeyCode::Esc => {
+                app.input.clear();
+                app.input_mode = InputMode::Normal;
+            }
+            _ => {}
+        },
+
+        // ── Options text entry ────────────────────────────────────────────
+        InputMode::EditingOption => match key.code {
+            KeyCode::Enter => {
+                let value = app.input.trim().to_string();
+                match apply_option_input(app, OptionItem::ALL[app.options_cursor], value) {
+                    Ok(()) => {
                         app.save_config();
+                        app.input.clear();
+                        app.error_message = None;
+                        app.input_mode = InputMode::Options;
                     }
-                    _ => {}
+                    Err(e) => app.error_message = Some(e.to_string()),
                 }
             }
+            KeyCode::Char(c) => { app.input.push(c); app.error_message = None; }
+            KeyCode::Backspace => { app.input.pop(); }
+            KeyCode::Esc => {
+                app.input.clear();
+                app.error_message = None;
+                app.input_mode = InputMode::Options;
+            }
             _ => {}
         },
 
@@ -274,33 +351,32 @@ pub async fn handle_key_event(
                 } else if app.config.projects.iter().any(|p| p.name == name) {
                     app.error_message = Some(format!("Project '{}' already exists.", name));
                 } else {
-                    let branch = branch_from_name(&name);
-                    app.input.clear();
-                    // Create the project immediately, then drop into AddingRepo
-                    let folder = Project::make_folder_path(&name);
-                    let project = Project {
-                        name: name.clone(),
-                        branch,
-                        worktrees: Vec::new(),
-                        folder: folder.clone(),
-                    };
-                    let _ = project.create_folder();
-                    app.config.projects.push(project);
-                    let new_p_idx = app.config.projects.len() - 1;
-                    app.expanded_projects.insert(new_p_idx);
-                    app.save_config();
-                    let items = app.get_tree_items();
-                    if let Some(idx) = items.iter().position(|(_, s, _)| *s == Selection::Project(new_p_idx)) {
-                        app.tree_state.select(Some(idx));
+                    match app.derive_branch(&name) {
+                        Ok(branch) => {
+                            // Offer the derived branch for editing before anything is created
+                            app.pending_project_name = name;
+                            app.input = branch;
+                            app.error_message = None;
+                            app.input_mode = InputMode::EditingProjectBranch;
+                        }
+                        Err(e) => app.error_message = Some(e.to_string()),
                     }
-                    // Immediately enter repo picker for the new project
-                    app.adding_to_project = Some(new_p_idx);
-                    app.fuzzy_cursor = None;
-                    app.update_fuzzy_results();
-                    app.input_mode = InputMode::AddingRepo;
-                    app.error_message = None;
                 }
             }
+            KeyCode::Up if !app.config.templates.is_empty() => {
+                app.template_cursor = match app.template_cursor {
+                    None => Some(app.config.templates.len() - 1),
+                    Some(0) => None,
+                    Some(i) => Some(i - 1),
+                };
+            }
+            KeyCode::Down if !app.config.templates.is_empty() => {
+                app.template_cursor = match app.template_cursor {
+                    None => Some(0),
+                    Some(i) if i + 1 < app.config.templates.len() => Some(i + 1),
+                    Some(_) => None,
+                };
+            }
             KeyCode::Char(c) => { app.input.push(c); app.error_message = None; }
             KeyCode::Backspace => { app.input.pop(); }
             KeyCode::Esc => {
@@ -311,13 +387,38 @@ pub async fn handle_key_event(
             _ => {}
         },
 
+        // ── Project branch ────────────────────────────────────────────────
+        InputMode::EditingProjectBranch => match key.code {
+            KeyCode::Enter => {
+                let branch = app.input.trim().to_string();
+                if branch.is_empty() {
+                    app.error_message = Some("Branch cannot be empty.".to_string());
+                } else if branch.contains(char::is_whitespace) {
+                    app.error_message = Some("Branch cannot contain spaces.".to_string());
+                } else {
+                    let name = std::mem::take(&mut app.pending_project_name);
+                    app.input.clear();
+                    handle_create_project(app, name, branch);
+                }
+            }
+            KeyCode::Char(c) => { app.input.push(c); app.error_message = None; }
+            KeyCode::Backspace => { app.input.pop(); }
+            KeyCode::Esc => {
+                // Back to the name, keeping what was typed
+                app.input = std::mem::take(&mut app.pending_project_name);
+                app.error_message = None;
+                app.input_mode = InputMode::AddingProjectName;
+            }
+            _ => {}
+        },
+
         // ── Fuzzy repo picker ─────────────────────────────────────────────
         InputMode::AddingRepo => match key.code {
             KeyCode::Tab => {
                 // Complete into the highlighted suggestion (or the first one)
                 let target = app.fuzzy_cursor
                     .filter(|&i| i < app.fuzzy_results.len())
-                    .or_else(|| if !app.fuzzy_results.is_empty() { Some(0) } else { None });
+                    .or(if !app.fuzzy_results.is_empty() { Some(0) } else { None });
                 if let Some(i) = target {
                     let mut path = app.fuzzy_results[i].path.to_string_lossy().to_string();
                     // Append trailing slash for directories so the user can keep browsing
@@ -340,21 +441,17 @@ pub async fn handle_key_event(
                 app.error_message = None;
                 app.update_fuzzy_results();
             }
-            KeyCode::Up => {
-                if !app.fuzzy_results.is_empty() {
-                    app.fuzzy_cursor = Some(match app.fuzzy_cursor {
-                        None | Some(0) => app.fuzzy_results.len() - 1,
-                        Some(i) => i - 1,
-                    });
-                }
+            KeyCode::Up if !app.fuzzy_results.is_empty() => {
+                app.fuzzy_cursor = Some(match app.fuzzy_cursor {
+                    None | Some(0) => app.fuzzy_results.len() - 1,
+                    Some(i) => i - 1,
+                });
             }
-            KeyCode::Down => {
-                if !app.fuzzy_results.is_empty() {
-                    app.fuzzy_cursor = Some(match app.fuzzy_cursor {
-                        None => 0,
-                        Some(i) => (i + 1) % app.fuzzy_results.len(),
-                    });
-                }
+            KeyCode::Down if !app.fuzzy_results.is_empty() => {
+                app.fuzzy_cursor = Some(match app.fuzzy_cursor {
+                    None => 0,
+                    Some(i) => (i + 1) % app.fuzzy_results.len(),
+                });
             }
             KeyCode::Enter => {
                 handle_add_repo(app).await?;
@@ -399,8 +496,8 @@ pub async fn handle_key_event(
                 };
 
                 match app.get_selected_selection() {
-                    Some(sel @ Selection::Worktree(p_idx, w_idx)) => {
-                        handle_push_single(app, sel, p_idx, w_idx, commit_msg);
+                    Some(Selection::Worktree(p_idx, w_idx)) => {
+                        handle_push_single(app, p_idx, w_idx, commit_msg);
                     }
                     Some(Selection::Project(p_idx)) => {
                         handle_push_project(app, p_idx, commit_msg);
@@ -425,19 +522,437 @@ pub async fn handle_key_event(
     Ok(AppState::Continue)
 }
 
+/// Selects the session at `idx` in the switcher in the tree and attaches to it.
+fn jump_to_session(app: &mut App, idx: usize) {
+    let Some(entry) = app.session_entries.get(idx) else { return };
+    let (sel, tab) = (entry.sel, entry.tab);
+    let Some(tabs) = app.sessions.get_mut(&sel) else { return };
+    tabs.active = tab.min(tabs.tabs.len() - 1);
+    app.select_in_tree(sel);
+    app.input_mode = InputMode::Terminal;
+    if let Some(code) = app.session(sel).and_then(|s| s.exit_code) {
+        app.terminal_warning = Some(format!(
+            "Process exited with code {}. Enter restarts it; any other key closes the tab.", code
+        ));
+    }
+}
+
+/// Attaches to the active tab on `sel`, restarting it if its process has exited, or
+/// opens a shell if there are no tabs. Reports failures in `error_message`.
+/// Carries out `action` on the selected row, from its key or from the action palette.
+fn perform(app: &mut App, action: Action) -> AppState {
+    match action {
+        Action::Quit => return AppState::Quit,
+
+        Action::NewProject => {
+            app.input_mode = InputMode::AddingProjectName;
+            app.input.clear();
+            app.pending_project_name.clear();
+            app.template_cursor = None;
+            app.error_message = None;
+            app.full_error_detail = None;
+        }
+
+        // Add a repo to the selected project (opens fuzzy path picker)
+        Action::AddRepo => {
+            if let Some(sel @ Selection::Project(p_idx)) = app.get_selected_selection() {
+                if reject_while_hooks_run(app, sel) {
+                    return AppState::Continue;
+                }
+                app.adding_to_project = Some(p_idx);
+                app.fuzzy_cursor = None;
+                app.input.clear();
+                app.update_fuzzy_results();
+                app.input_mode = InputMode::AddingRepo;
+                app.error_message = None;
+                app.full_error_detail = None;
+            } else {
+                app.error_message = Some("Select a project first.".to_string());
+            }
+        }
+
+        // Remove project or worktree — requires confirmation
+        Action::Remove => {
+            if let Some(sel) = app.get_selected_selection() {
+                if reject_while_hooks_run(app, sel) {
+                    return AppState::Continue;
+                }
+                app.pending_delete = Some(sel);
+                app.input_mode = InputMode::ConfirmDelete;
+            }
+        }
+
+        // Open terminal (worktree or project level)
+        Action::Terminal => {
+            if let Some(sel) = app.get_selected_selection() {
+                if app.config.settings.multiplexer().is_external() {
+                    return open_mux_session(app, sel);
+                }
+                if open_session(app, sel) {
+                    app.input_mode = InputMode::Terminal;
+                }
+            }
+        }
+
+        // Command palette: user commands for the selected project or worktree
+        Action::RunCommand => {
+            if let Some(sel) = app.get_selected_selection() {
+                app.palette_commands = app.commands_for(sel);
+                app.palette_cursor = 0;
+                app.error_message = None;
+                app.input_mode = InputMode::Commands;
+            }
+        }
+
+        // Open the project folder or worktree in the editor
+        Action::Editor => {
+            if let Some(sel) = app.get_selected_selection() {
+                return open_in_editor(app, sel);
+            }
+        }
+
+        // New terminal tab, optionally running a command instead of the shell
+        Action::NewTab => {
+            if app.get_selected_selection().is_none() {
+                app.error_message = Some("Select a project or worktree first.".to_string());
+            } else if app.config.settings.multiplexer().is_external() {
+                app.error_message = Some("Tabs need the built-in terminal; pick it as the terminal backend in Options.".to_string());
+            } else {
+                app.input.clear();
+                app.error_message = None;
+                app.full_error_detail = None;
+                app.input_mode = InputMode::NewTabCommand;
+            }
+        }
+
+        // Cycle the selected row's terminal tabs
+        Action::PreviousTab | Action::NextTab => {
+            if let Some(sel) = app.get_selected_selection()
+                && let Some(tabs) = app.sessions.get_mut(&sel)
+            {
+                tabs.cycle(if action == Action::NextTab { 1 } else { -1 });
+            }
+        }
+
+        // Split the focused pane side by side or stacked; close it
+        Action::SplitSideBySide | Action::SplitStacked => {
+            if app.config.settings.multiplexer().is_external() {
+                app.error_message = Some("Splits need the built-in terminal; pick it as the terminal backend in Options.".to_string());
+            } else {
+                app.panes.split(if action == Action::SplitSideBySide {
+                    SplitDirection::Horizontal
+                } else {
+                    SplitDirection::Vertical
+                });
+                app.error_message = None;
+            }
+        }
+        Action::ClosePane => {
+            let focused = app.panes.focused;
+            if app.panes.close(focused) {
+                app.focus_pane(app.panes.focused);
+            }
+        }
+
+        // Push: single worktree or all worktrees in project
+        Action::Push => {
+            if let Some(sel) = app.get_selected_selection() {
+                if reject_while_hooks_run(app, sel) {
+                    return AppState::Continue;
+                }
+                app.input_mode = InputMode::EditingCommitMessage;
+                app.input.clear();
+                app.error_message = None;
+                app.full_error_detail = None;
+            }
+        }
+
+        // Diff (worktree only)
+        Action::Diff => {
+            if let Some(sel @ Selection::Worktree(p_idx, w_idx)) = app.get_selected_selection() {
+                match app.config.projects[p_idx].worktrees[w_idx].get_diff() {
+                    Ok(out) => {
+                        let mut full_output = Vec::new();
+                        full_output.extend_from_slice(&out.stdout);
+                        full_output.extend_from_slice(&out.stderr);
+
+                        if let Some(session) = app.session(sel) {
+                            session.parser.lock().unwrap().process(&full_output);
+                            app.input_mode = InputMode::ViewingDiff;
+                        } else {
+                            app.command_output = String::from_utf8_lossy(&full_output)
+                                .lines().map(String::from).collect();
+                            if !out.status.success() {
+                                app.error_message = Some("Failed to get diff".to_string());
+                                app.full_error_detail = Some(app.command_output.join("\n"));
+                            } else if app.command_output.is_empty() {
+                                app.error_message = Some("No changes to diff.".to_string());
+                            } else {
+                                app.input_mode = InputMode::ViewingDiff;
+                                app.error_message = None;
+                                app.full_error_detail = None;
+                                app.diff_scroll_offset = 0;
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        app.error_message = Some("System error getting diff".to_string());
+                        app.full_error_detail = Some(e.to_string());
+                    }
+                }
+            }
+        }
+
+        Action::Options => {
+            app.input_mode = InputMode::Options;
+            app.options_cursor = 0;
+            app.error_message = None;
+        }
+
+        Action::Sessions => {
+            app.session_cursor = 0;
+            app.refresh_session_entries();
+            app.error_message = None;
+            app.input_mode = InputMode::Sessions;
+        }
+
+        Action::Help => {
+            app.input_mode = InputMode::Help;
+            app.error_message = None;
+        }
+
+        Action::Palette => {
+            app.input.clear();
+            app.action_cursor = 0;
+            app.update_action_matches();
+            app.error_message = None;
+            app.input_mode = InputMode::Actions;
+        }
+
+        // Expand/collapse project
+        Action::ToggleExpand => {
+            if let Some(Selection::Project(p_idx)) = app.get_selected_selection() {
+                app.toggle_project_expand(p_idx);
+            }
+        }
+
+        Action::ExportLog => export_log(app),
+
+        // Tree motions
+        Action::MoveDown => app.next(),
+        Action::MoveUp => app.previous(),
+        Action::First => app.select_first(),
+        Action::Last => app.select_last(),
+        Action::PageDown => app.move_by(app.tree_page() as isize),
+        Action::PageUp => app.move_by(-(app.tree_page() as isize)),
+        Action::Collapse => app.collapse_selected(),
+        Action::Expand => app.expand_selected(),
+        Action::NextProject => app.jump_project(true),
+        Action::PreviousProject => app.jump_project(false),
+        Action::NextDirty => app.next_dirty_worktree(),
+
+        // Tree filter
+        Action::Filter => {
+            app.input = app.tree_filter.query.clone();
+            app.error_message = None;
+            app.input_mode = InputMode::Filtering;
+        }
+        Action::FilterChanges => {
+            let filter = TreeFilter { changes_only: !app.tree_filter.changes_only, ..app.tree_filter.clone() };
+            app.set_tree_filter(filter);
+        }
+        Action::FilterUnpushed => {
+            let filter = TreeFilter { unpushed_only: !app.tree_filter.unpushed_only, ..app.tree_filter.clone() };
+            app.set_tree_filter(filter);
+        }
+    }
+    AppState::Continue
+}
+
+/// Narrows the tree to the query typed so far.
+fn set_filter_query(app: &mut App) {
+    let filter = TreeFilter { query: app.input.clone(), ..app.tree_filter.clone() };
+    app.set_tree_filter(filter);
+}
+
+/// Writes the last error's detail (or the status line) to `/tmp/workman.log`.
+fn export_log(app: &mut App) {
+    if let Some(detail) = &app.full_error_detail {
+        let _ = fs::write("/tmp/workman.log", detail);
+        app.error_message = Some("Log exported to /tmp/workman.log".to_string());
+    } else if let Some(err) = &app.error_message {
+        let _ = fs::write("/tmp/workman.log", err);
+        app.error_message = Some("Status exported to /tmp/workman.log".to_string());
+    }
+}
+
+fn open_session(app: &mut App, sel: Selection) -> bool {
+    let (width, height) = app.pane_size();
+    let result = match app.session(sel) {
+        Some(session) if session.exit_code.is_none() => return true,
+        Some(_) => app.restart_session(sel, width, height),
+        None => app.start_session(sel, None, width, height),
+    };
+    match result {
+        Ok(()) => true,
+        Err(e) => {
+            app.error_message = Some(format!("Failed to start session: {}", e));
+            false
+        }
+    }
+}
+
+/// Pasted text goes to the attached session, or into the active text prompt.
+pub fn handle_paste_event(text: String, app: &mut App) {
+    match app.input_mode {
+        InputMode::Terminal => terminal_handler::handle_terminal_paste(&text, app),
+        InputMode::AddingProjectName
+        | InputMode::EditingProjectBranch
+        | InputMode::EditingCommitMessage
+        | InputMode::EditingOption
+        | InputMode::ScrollbackSearch
+        | InputMode::RenamingSession => {
+            // Prompts are single-line; keep the first line only
+            app.input.push_str(text.lines().next().unwrap_or_default());
+            app.error_message = None;
+        }
+        InputMode::AddingRepo => {
+            app.input.push_str(text.lines().next().unwrap_or_default().trim());
+            app.fuzzy_cursor = None;
+            app.update_fuzzy_results();
+        }
+        InputMode::Actions => {
+            app.input.push_str(text.lines().next().unwrap_or_default());
+            app.action_cursor = 0;
+            app.update_action_matches();
+        }
+        InputMode::Filtering => {
+            app.input.push_str(text.lines().next().unwrap_or_default());
+            set_filter_query(app);
+        }
+        _ => {}
+    }
+}
+
+/// Mouse events go to an attached session that asked for them; otherwise a click in the
+/// tree selects the row, and a drag over a session's output selects text to copy.
+pub fn handle_mouse_event(mouse: MouseEvent, app: &mut App) {
+    // A click on another split pane focuses it
+    if matches!(app.input_mode, InputMode::Normal | InputMode::Terminal)
+        && mouse.kind == MouseEventKind::Down(MouseButton::Left)
+        && app.panes.len() > 1
+        && let Some(idx) = app.panes.layout(app.panel_area).iter().position(|(_, r)| {
+            mouse.column >= r.x && mouse.column < r.right() && mouse.row >= r.y && mouse.row < r.bottom()
+        })
+        && idx != app.panes.focused
+    {
+        app.focus_pane(idx);
+        return;
+    }
+
+    if app.input_mode == InputMode::Terminal && terminal_handler::handle_terminal_mouse_event(mouse, app) {
+        return;
+    }
+
+    let tree = app.tree_area;
+    let in_tree = mouse.column > tree.x && mouse.column < tree.right().saturating_sub(1)
+        && mouse.row > tree.y && mouse.row < tree.bottom().saturating_sub(1);
+    if app.input_mode == InputMode::Normal && in_tree {
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                let idx = app.tree_state.offset() + (mouse.row - tree.y - 1) as usize;
+                if idx < app.get_tree_items().len() {
+                    app.tree_state.select(Some(idx));
+                    app.mouse_selection = None;
+                    app.error_message = None;
+                    app.full_error_detail = None;
+                }
+            }
+            MouseEventKind::ScrollDown => app.next(),
+            MouseEventKind::ScrollUp => app.previous(),
+            _ => {}
+        }
+        return;
+    }
+
+    if matches!(app.input_mode, InputMode::Normal | InputMode::Terminal | InputMode::Scrollback)
+        && app.get_selected_selection().is_some_and(|sel| app.sessions.contains_key(&sel))
+    {
+        match mouse.kind {
+            // The wheel pages through history when the program doesn't take the mouse
+            MouseEventKind::ScrollUp | MouseEventKind::ScrollDown
+                if app.input_mode != InputMode::Normal =>
+            {
+                terminal_handler::handle_scrollback_wheel(mouse.kind == MouseEventKind::ScrollUp, app);
+            }
+            _ => terminal_handler::handle_selection_mouse_event(mouse, app),
+        }
+    }
+}
+
 // ── Helpers ───────────────────────────────────────────────────────────────────
 
-/// Removes an entire project: all worktrees, project folder, config entry.
+/// Validates and stores a text value entered for an Options row.
+fn apply_option_input(app: &mut App, item: OptionItem, value: String) -> Result<()> {
+    match item {
+        OptionItem::WorktreeLocation => {
+            if value.is_empty() || value == DEFAULT_WORKTREE_LOCATION {
+                app.config.settings.worktree_location = None;
+            } else {
+                validate_worktree_location(&value)?;
+                app.config.settings.worktree_location = Some(value);
+            }
+        }
+        OptionItem::BranchTemplate => {
+            if value.is_empty() || value == DEFAULT_BRANCH_TEMPLATE {
+                app.config.settings.branch_naming.template = None;
+            } else if !value.contains("{slug}") && !value.contains("{ticket}") {
+                return Err(anyhow::anyhow!("Template must contain {{slug}} or {{ticket}}."));
+            } else {
+                app.config.settings.branch_naming.template = Some(value);
+            }
+        }
+        OptionItem::DetachKey => {
+            if value.is_empty() {
+                app.config.settings.detach_key = None;
+            } else {
+                let key = KeyChord::parse(&value)?;
+                if key == DEFAULT_DETACH_KEY {
+                    app.config.settings.detach_key = None;
+                } else {
+                    app.config.settings.detach_key = Some(value.to_lowercase());
+                }
+            }
+        }
+        OptionItem::Editor => {
+            app.config.settings.editor = (!value.is_empty()).then_some(value);
+        }
+        OptionItem::Multiplexer | OptionItem::PersistentSessions => {}
+    }
+    Ok(())
+}
+
+/// Removes an entire project once the pre_remove hooks of all its worktrees pass; any
+/// failure keeps the whole project.
 fn handle_remove_project(app: &mut App, p_idx: usize) {
+    let project = &app.config.projects[p_idx];
+    let targets = project.worktrees.iter().map(|wt| (p_idx, wt.repo_name.clone(), wt.path.clone())).collect();
+    let then = AfterHooks::RemoveProject { project: project.folder.clone() };
+    app.command_output.clear();
+    run_hooks_then(app, HookPoint::PreRemove, targets, then);
+}
+
+/// Removes an entire project: all worktrees, project folder, config entry.
+fn remove_project(app: &mut App, p_idx: usize) {
     let project = app.config.projects[p_idx].clone();
     let mut errors: Vec<String> = Vec::new();
 
     // Remove each git worktree via its parent repo
     for wt in &project.worktrees {
-        if let Some(repo) = app.config.repos.iter().find(|r| r.name == wt.repo_name) {
-            if let Err(e) = repo.remove_worktree(&wt.path) {
-                errors.push(format!("[{}] remove worktree error: {}", wt.repo_name, e));
-            }
+        if let Some(repo) = app.config.repos.iter().find(|r| r.name == wt.repo_name)
+            && let Err(e) = repo.remove_worktree(&wt.path)
+        {
+            errors.push(format!("[{}] remove worktree error: {}", wt.repo_name, e));
         }
     }
 
@@ -446,11 +961,8 @@ fn handle_remove_project(app: &mut App, p_idx: usize) {
         errors.push(format!("remove project folder: {}", e));
     }
 
-    // Remove session if open
-    app.sessions.remove(&Selection::Project(p_idx));
-    for w_idx in 0..project.worktrees.len() {
-        app.sessions.remove(&Selection::Worktree(p_idx, w_idx));
-    }
+    // Close the project's sessions (and their shells)
+    app.remove_project_sessions(p_idx);
 
     app.config.projects.remove(p_idx);
     // Re-index expanded_projects
@@ -464,9 +976,8 @@ fn handle_remove_project(app: &mut App, p_idx: usize) {
 
     if errors.is_empty() {
         app.error_message = None;
-        app.command_output.clear();
     } else {
-        app.command_output = errors;
+        app.command_output.extend(errors);
         app.error_message = Some("Some errors during project removal (see output).".to_string());
     }
 
@@ -479,8 +990,17 @@ fn handle_remove_project(app: &mut App, p_idx: usize) {
     }
 }
 
-/// Removes a single worktree from a project.
+/// Removes a single worktree from a project once its pre_remove hooks pass.
 fn handle_remove_worktree(app: &mut App, p_idx: usize, w_idx: usize) {
+    let wt = &app.config.projects[p_idx].worktrees[w_idx];
+    let targets = vec![(p_idx, wt.repo_name.clone(), wt.path.clone())];
+    let then = AfterHooks::RemoveWorktree { worktree: wt.path.clone() };
+    app.command_output.clear();
+    run_hooks_then(app, HookPoint::PreRemove, targets, then);
+}
+
+/// Removes a single worktree from a project.
+fn remove_worktree(app: &mut App, p_idx: usize, w_idx: usize) {
     let wt = app.config.projects[p_idx].worktrees[w_idx].clone();
     let project_folder = app.config.projects[p_idx].folder.clone();
 
@@ -495,7 +1015,7 @@ fn handle_remove_worktree(app: &mut App, p_idx: usize, w_idx: usize) {
             let link = project_folder.join(&wt.repo_name);
             let _ = std::fs::remove_file(&link);
 
-            app.sessions.remove(&Selection::Worktree(p_idx, w_idx));
+            app.remove_worktree_session(p_idx, w_idx);
             app.config.projects[p_idx].worktrees.remove(w_idx);
             app.save_config();
             app.refresh_worktree_status();
@@ -521,7 +1041,7 @@ fn handle_remove_worktree(app: &mut App, p_idx: usize, w_idx: usize) {
             // Repo no longer in registry — remove from project anyway
             let link = project_folder.join(&wt.repo_name);
             let _ = std::fs::remove_file(&link);
-            app.sessions.remove(&Selection::Worktree(p_idx, w_idx));
+            app.remove_worktree_session(p_idx, w_idx);
             app.config.projects[p_idx].worktrees.remove(w_idx);
             app.save_config();
             app.refresh_worktree_status();
@@ -583,44 +1103,361 @@ async fn handle_add_repo(app: &mut App) -> Result<()> {
                 r
             };
 
-            let branch = app.config.projects[p_idx].branch.clone();
-            match repo.add_worktree(&branch) {
-                Ok((out, wt_path)) if out.status.success() => {
-                    let wt = ProjectWorktree { repo_name: repo.name.clone(), path: wt_path };
-                    let _ = app.config.projects[p_idx].add_symlink(&wt);
-                    app.config.projects[p_idx].worktrees.push(wt);
+            create_worktrees(app, p_idx, vec![(repo, None)], None);
+        }
+    }
+    Ok(())
+}
+
+/// Creates the project folder and config entry, then either builds the selected template's
+/// worktrees or drops into the repo picker.
+fn handle_create_project(app: &mut App, name: String, branch: String) {
+    let template = app.template_cursor.and_then(|i| app.config.templates.get(i).cloned());
+    let folder = Project::make_folder_path(&name);
+    let project = Project {
+        name,
+        branch,
+        worktrees: Vec::new(),
+        folder,
+    };
+    let _ = project.create_folder();
+    app.config.projects.push(project);
+    let new_p_idx = app.config.projects.len() - 1;
+    app.expanded_projects.insert(new_p_idx);
+    app.save_config();
+    let items = app.get_tree_items();
+    if let Some(idx) = items.iter().position(|(_, s, _)| *s == Selection::Project(new_p_idx)) {
+        app.tree_state.select(Some(idx));
+    }
+    app.error_message = None;
+    if let Some(template) = template {
+        // Templates create every worktree up front; no picker needed
+        app.input_mode = InputMode::Normal;
+        handle_create_from_template(app, new_p_idx, &template);
+    } else {
+        // Immediately enter repo picker for the new project
+        app.adding_to_project = Some(new_p_idx);
+        app.fuzzy_cursor = None;
+        app.update_fuzzy_results();
+        app.input_mode = InputMode::AddingRepo;
+    }
+}
+
+/// Creates worktrees of `repos` (on their bases) in project `p_idx` once their pre_create
+/// hooks pass. `template` is set for a template's repos; see `AfterHooks::Create`.
+fn create_worktrees(app: &mut App, p_idx: usize, repos: Vec<(Repo, Option<String>)>, template: Option<usize>) {
+    if reject_while_hooks_run(app, Selection::Project(p_idx)) {
+        return;
+    }
+    let project = &app.config.projects[p_idx];
+    let location = app.config.settings.worktree_location();
+    let targets = repos.iter()
+        .map(|(repo, _)| (p_idx, repo.name.clone(), repo.worktree_path(&project.branch, location)))
+        .collect();
+    let then = AfterHooks::Create { project: project.folder.clone(), repos, template };
+    run_hooks_then(app, HookPoint::PreCreate, targets, then);
+}
+
+/// Creates `repo`'s worktree on the project branch and wires it into the project, then
+/// kicks off setup. On failure sets `error_message` and returns false.
+fn add_project_worktree(app: &mut App, p_idx: usize, repo: &Repo, base: Option<&str>) -> bool {
+    let branch = app.config.projects[p_idx].branch.clone();
+    let location = app.config.settings.worktree_location().to_string();
+
+    match repo.add_worktree(&branch, &location, base) {
+        Ok((out, wt_path)) if out.status.success() => {
+            let wt = ProjectWorktree { repo_name: repo.name.clone(), path: wt_path.clone() };
+            let _ = app.config.projects[p_idx].add_symlink(&wt);
+            app.config.projects[p_idx].worktrees.push(wt);
+            app.save_config();
+            app.refresh_worktree_status();
+            app.error_message = None;
+            app.full_error_detail = None;
+            run_worktree_setup(app, p_idx, repo, wt_path);
+            true
+        }
+        Ok((out, _)) => {
+            // Save cache even on worktree failure
+            app.save_config();
+            let stderr = String::from_utf8_lossy(&out.stderr).to_string();
+            app.error_message = Some(format!("Worktree error: {}", stderr.trim()));
+            app.full_error_detail = Some(stderr);
+            false
+        }
+        Err(e) => {
+            app.save_config();
+            app.error_message = Some(format!("Error: {}", e));
+            app.full_error_detail = Some(e.to_string());
+            false
+        }
+    }
+}
+
+/// Creates a worktree for every repo listed in `template` in the (new) project.
+fn handle_create_from_template(app: &mut App, p_idx: usize, template: &ProjectTemplate) {
+    app.command_output.clear();
+    let mut repos = Vec::new();
+    let mut failures = 0;
+
+    for entry in &template.repos {
+        match resolve_template_repo(app, &entry.repo) {
+            Ok(repo) => repos.push((repo, entry.base.clone())),
+            Err(e) => {
+                failures += 1;
+                app.command_output.push(format!("✗ [{}]  {}", entry.repo, e));
+            }
+        }
+    }
+    create_worktrees(app, p_idx, repos, Some(failures));
+}
+
+/// Looks up a template repo by registered name, or registers it if given as a path.
+fn resolve_template_repo(app: &mut App, repo: &str) -> Result<Repo> {
+    if let Some(existing) = app.config.repos.iter().find(|r| r.name == repo) {
+        return Ok(existing.clone());
+    }
+    let path = PathBuf::from(repo);
+    Config::validate_repo_path(&path)
+        .map_err(|_| anyhow::anyhow!("not a registered repo or git repository path"))?;
+    let abs_path = fs::canonicalize(&path)?;
+    if let Some(existing) = app.config.repos.iter().find(|r| r.path == abs_path) {
+        return Ok(existing.clone());
+    }
+    let name = abs_path.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let r = Repo { name, path: abs_path };
+    app.config.repos.push(r.clone());
+    Ok(r)
+}
+
+/// Applies the repo's `.workman.toml` setup to a freshly created worktree: copies and
+/// symlinks files synchronously, then streams setup commands and post_create hooks
+/// in the background.
+fn run_worktree_setup(app: &mut App, p_idx: usize, repo: &Repo, wt_path: PathBuf) {
+    let repo_config = match RepoConfig::load(&repo.path) {
+        Ok(config) => config,
+        Err(e) => {
+            app.command_output.push(format!("[{}] {}", repo.name, e));
+            app.error_message = Some(format!("Skipped setup for [{}]: invalid {}", repo.name, REPO_CONFIG_FILE));
+            return;
+        }
+    };
+
+    for line in repo_config.setup.link_files(&repo.path, &wt_path) {
+        app.command_output.push(format!("[{}] {}", repo.name, line));
+    }
+
+    let mut commands = repo_config.setup.commands;
+    commands.extend(hooks::commands_for(HookPoint::PostCreate, &app.config.settings.hooks, &repo_config.hooks));
+    if !commands.is_empty() {
+        let project = &app.config.projects[p_idx];
+        let env = HookContext {
+            project: &project.name,
+            repo: &repo.name,
+            branch: &project.branch,
+            worktree: &wt_path,
+        }.env(HookPoint::PostCreate);
+        app.setting_up.insert(wt_path.clone());
+        tasks::spawn_setup(wt_path, repo.name.clone(), commands, env, app.task_tx.clone());
+    }
+}
+
+/// Runs the global and repo hooks for `point` on each target (project index, repo name,
+/// worktree) in the background, then resumes `then` from `TaskEvent::HooksFinished`. With
+/// no hooks to run, `then` resumes straight away.
+fn run_hooks_then(app: &mut App, point: HookPoint, targets: Vec<(usize, String, PathBuf)>, then: AfterHooks) {
+    let mut jobs = Vec::new();
+    // A repo whose .workman.toml can't be read fails without running anything
+    let mut results = Vec::new();
+    for (p_idx, repo_name, worktree) in targets {
+        let repo = app.config.repos.iter().find(|r| r.name == repo_name);
+        let repo_hooks = match repo.map(|r| RepoConfig::load(&r.path)) {
+            Some(Ok(config)) => config.hooks,
+            Some(Err(e)) => {
+                app.command_output.push(format!("[{}] {}", repo_name, e));
+                results.push((repo_name, false));
+                continue;
+            }
+            None => Default::default(),
+        };
+        let commands = hooks::commands_for(point, &app.config.settings.hooks, &repo_hooks);
+        if commands.is_empty() {
+            continue;
+        }
+
+        // Hooks run inside the worktree; before it exists (pre_create) they run in the main checkout
+        let cwd = if worktree.exists() {
+            worktree.clone()
+        } else {
+            repo.map(|r| r.path.clone())
+                .or_else(dirs::home_dir)
+                .unwrap_or_else(|| PathBuf::from("."))
+        };
+        let project = &app.config.projects[p_idx];
+        let env = HookContext {
+            project: &project.name,
+            repo: &repo_name,
+            branch: &project.branch,
+            worktree: &worktree,
+        }.env(point);
+        jobs.push(HookJob { label: repo_name, cwd, commands, env });
+    }
+
+    let cancelled = then.all_or_nothing() && results.iter().any(|(_, ok)| !ok);
+    if jobs.is_empty() || cancelled {
+        resume_after_hooks(app, point, results, then);
+    } else {
+        if let Some(row) = then.row() {
+            app.running_hooks.insert(row.to_path_buf());
+        }
+        tasks::spawn_hooks(point, jobs, results, then, app.task_tx.clone());
+    }
+}
+
+/// Sets an error and returns true if `sel` is still waiting on hooks, so the same action
+/// isn't started twice.
+fn reject_while_hooks_run(app: &mut App, sel: Selection) -> bool {
+    if !app.hooks_running(sel) {
+        return false;
+    }
+    app.error_message = Some("Hooks are still running here — wait for them to finish.".to_string());
+    true
+}
+
+/// Applies a background task event; a finished hook run resumes the action it held up.
+pub fn handle_task_event(event: TaskEvent, app: &mut App) {
+    match event {
+        TaskEvent::HooksFinished { point, results, then } => {
+            if let Some(row) = then.row() {
+                app.running_hooks.remove(row);
+            }
+            resume_after_hooks(app, point, results, then)
+        }
+        event => app.handle_task_event(event),
+    }
+}
+
+/// Carries on with the action `point`'s hooks were holding up, now that `results` says
+/// whose hooks passed. Worktrees without hooks have no result and pass.
+fn resume_after_hooks(app: &mut App, point: HookPoint, results: Vec<(String, bool)>, then: AfterHooks) {
+    let passed = |name: &str| results.iter().all(|(label, ok)| label != name || *ok);
+    let failed: Vec<&str> = results.iter().filter(|(_, ok)| !ok).map(|(label, _)| label.as_str()).collect();
+    let project_index = |app: &App, folder: &Path| app.config.projects.iter().position(|p| p.folder == folder);
+    let worktree_index = |app: &App, path: &Path| {
+        app.config.projects.iter().enumerate().find_map(|(p_idx, p)| {
+            p.worktrees.iter().position(|wt| wt.path == path).map(|w_idx| (p_idx, w_idx))
+        })
+    };
+
+    match then {
+        AfterHooks::Create { project, repos, template } => {
+            let Some(p_idx) = project_index(app, &project) else {
+                app.save_config();
+                app.error_message = Some("The project was removed before its worktrees were created.".to_string());
+                return;
+            };
+            let mut failures = template.unwrap_or(0);
+            let total = repos.len() + failures;
+            for (repo, base) in &repos {
+                let created = if passed(&repo.name) {
+                    add_project_worktree(app, p_idx, repo, base.as_deref())
+                } else {
                     app.save_config();
-                    app.refresh_worktree_status();
+                    app.error_message = Some(format!("pre_create hook failed — no worktree created for [{}]", repo.name));
+                    app.full_error_detail = Some(app.command_output.join("\n"));
+                    false
+                };
+                if template.is_some() {
+                    if created {
+                        app.command_output.push(format!("✓ [{}]  worktree created", repo.name));
+                    } else {
+                        failures += 1;
+                        let reason = app.error_message.take().unwrap_or_default();
+                        app.command_output.push(format!("✗ [{}]  {}", repo.name, reason));
+                    }
+                } else if created && app.input_mode == InputMode::AddingRepo && app.adding_to_project == Some(p_idx) {
                     // Clear input, reset cursor, recompute suggestions for next repo
                     app.input.clear();
                     app.fuzzy_cursor = None;
                     app.update_fuzzy_results();
+                }
+            }
+            if template.is_some() {
+                if failures == 0 {
                     app.error_message = None;
                     app.full_error_detail = None;
+                } else {
+                    app.error_message = Some(format!("{} of {} template repos failed (see output)", failures, total));
+                    app.full_error_detail = Some(app.command_output.join("\n"));
                 }
-                Ok((out, _)) => {
-                    // Save cache even on worktree failure
-                    app.save_config();
-                    let stderr = String::from_utf8_lossy(&out.stderr).to_string();
-                    app.error_message = Some(format!("Worktree error: {}", stderr.trim()));
-                    app.full_error_detail = Some(stderr);
-                }
-                Err(e) => {
-                    app.save_config();
-                    app.error_message = Some(format!("Error: {}", e));
-                    app.full_error_detail = Some(e.to_string());
-                }
+            }
+        }
+        AfterHooks::PushWorktree { worktree, commit_msg } => {
+            let Some((p_idx, w_idx)) = worktree_index(app, &worktree) else {
+                app.error_message = Some("The worktree was removed before it was pushed.".to_string());
+                return;
+            };
+            if !failed.is_empty() {
+                app.error_message = Some("pre_push hook failed — push aborted (Ctrl+L to export log)".to_string());
+                app.full_error_detail = Some(app.command_output.join("\n"));
+                return;
+            }
+            push_worktree(app, p_idx, w_idx, commit_msg);
+        }
+        AfterHooks::PushProject { project, commit_msg } => {
+            let Some(p_idx) = project_index(app, &project) else {
+                app.error_message = Some("The project was removed before it was pushed.".to_string());
+                return;
+            };
+            push_project(app, p_idx, commit_msg, &passed);
+        }
+        AfterHooks::RemoveWorktree { worktree } => {
+            let Some((p_idx, w_idx)) = worktree_index(app, &worktree) else { return };
+            if !failed.is_empty() {
+                app.error_message = Some("pre_remove hook failed — worktree kept (see output)".to_string());
+                app.full_error_detail = Some(app.command_output.join("\n"));
+                return;
+            }
+            remove_worktree(app, p_idx, w_idx);
+        }
+        AfterHooks::RemoveProject { project } => {
+            let Some(p_idx) = project_index(app, &project) else { return };
+            if !failed.is_empty() {
+                app.error_message = Some("pre_remove hook failed — project kept (see output)".to_string());
+                app.full_error_detail = Some(app.command_output.join("\n"));
+                return;
+            }
+            remove_project(app, p_idx);
+        }
+        AfterHooks::Report => {
+            if !failed.is_empty() {
+                app.error_message = Some(format!(
+                    "{} hook failed in [{}] (see output)", point.name(), failed.join("], [")
+                ));
+                app.full_error_detail = Some(app.command_output.join("\n"));
             }
         }
     }
-    Ok(())
 }
 
-/// Push a single worktree.
-fn handle_push_single(app: &mut App, sel: Selection, p_idx: usize, w_idx: usize, commit_msg: Option<String>) {
-    match app.config.projects[p_idx].worktrees[w_idx].push(commit_msg) {
+/// Pushes a single worktree once its pre_push hooks pass.
+fn handle_push_single(app: &mut App, p_idx: usize, w_idx: usize, commit_msg: Option<String>) {
+    let wt = &app.config.projects[p_idx].worktrees[w_idx];
+    let targets = vec![(p_idx, wt.repo_name.clone(), wt.path.clone())];
+    let then = AfterHooks::PushWorktree { worktree: wt.path.clone(), commit_msg };
+    app.command_output.clear();
+    run_hooks_then(app, HookPoint::PrePush, targets, then);
+}
+
+/// Push a single worktree, then run its post_push hooks.
+fn push_worktree(app: &mut App, p_idx: usize, w_idx: usize, commit_msg: Option<String>) {
+    let sel = Selection::Worktree(p_idx, w_idx);
+    let wt = app.config.projects[p_idx].worktrees[w_idx].clone();
+    // The pre_push hooks' output
+    let hook_output = std::mem::take(&mut app.command_output);
+
+    match wt.push(commit_msg) {
         Ok((add_out, commit_out, push_out)) => {
-            let mut full_output = Vec::new();
+            let mut full_output: Vec<u8> = hook_output.iter().flat_map(|l| format!("{}\n", l).into_bytes()).collect();
             full_output.extend_from_slice(&add_out.stdout);
             full_output.extend_from_slice(&add_out.stderr);
             full_output.extend_from_slice(&commit_out.stdout);
@@ -628,7 +1465,7 @@ fn handle_push_single(app: &mut App, sel: Selection, p_idx: usize, w_idx: usize,
             full_output.extend_from_slice(&push_out.stdout);
             full_output.extend_from_slice(&push_out.stderr);
 
-            if let Some(session) = app.sessions.get(&sel) {
+            if let Some(session) = app.session(sel) {
                 session.parser.lock().unwrap().process(&full_output);
             } else {
                 app.command_output = String::from_utf8_lossy(&full_output).lines().map(String::from).collect();
@@ -638,11 +1475,13 @@ fn handle_push_single(app: &mut App, sel: Selection, p_idx: usize, w_idx: usize,
                 app.refresh_worktree_status();
                 app.error_message = None;
                 app.full_error_detail = None;
-                if !app.sessions.contains_key(&sel) {
+                if app.session(sel).is_none() {
                     let mut success = b"Push successful!\n".to_vec();
                     success.extend(full_output);
                     app.command_output = String::from_utf8_lossy(&success).lines().map(String::from).collect();
                 }
+                let targets = vec![(p_idx, wt.repo_name.clone(), wt.path.clone())];
+                run_hooks_then(app, HookPoint::PostPush, targets, AfterHooks::Report);
             } else {
                 app.error_message = Some("Push failed (Ctrl+L to export log)".to_string());
                 app.full_error_detail = Some(app.command_output.join("\n"));
@@ -655,13 +1494,32 @@ fn handle_push_single(app: &mut App, sel: Selection, p_idx: usize, w_idx: usize,
     }
 }
 
-/// Push all worktrees in a project.
+/// Pushes all worktrees in a project, each once its pre_push hooks pass.
 fn handle_push_project(app: &mut App, p_idx: usize, commit_msg: Option<String>) {
+    let project = &app.config.projects[p_idx];
+    let targets = project.worktrees.iter().map(|wt| (p_idx, wt.repo_name.clone(), wt.path.clone())).collect();
+    let then = AfterHooks::PushProject { project: project.folder.clone(), commit_msg };
+    app.command_output.clear();
+    run_hooks_then(app, HookPoint::PrePush, targets, then);
+}
+
+/// Push the worktrees of a project whose pre_push hooks `passed`, then run the post_push
+/// hooks of those that pushed.
+fn push_project(app: &mut App, p_idx: usize, commit_msg: Option<String>, passed: &dyn Fn(&str) -> bool) {
     let worktrees = app.config.projects[p_idx].worktrees.clone();
     let mut results: Vec<String> = Vec::new();
     let mut all_success = true;
+    let mut pushed_worktrees = Vec::new();
+
+    // The pre_push hooks' output
+    let hook_output = std::mem::take(&mut app.command_output);
 
     for wt in &worktrees {
+        if !passed(&wt.repo_name) {
+            all_success = false;
+            results.push(format!("✗ [{}]  pre_push hook failed", wt.repo_name));
+            continue;
+        }
         match wt.push(commit_msg.clone()) {
             Ok((_, commit_out, push_out)) => {
                 let pushed = push_out.status.success();
@@ -681,6 +1539,9 @@ fn handle_push_project(app: &mut App, p_idx: usize, commit_msg: Option<String>)
                 } else {
                     "pushed".to_string()
                 };
+                if pushed {
+                    pushed_worktrees.push((p_idx, wt.repo_name.clone(), wt.path.clone()));
+                }
 
                 results.push(format!("{} [{}]  {}", status_icon, wt.repo_name, detail));
             }
@@ -692,6 +1553,10 @@ fn handle_push_project(app: &mut App, p_idx: usize, commit_msg: Option<String>)
     }
 
     app.command_output = results;
+    if !hook_output.is_empty() {
+        app.command_output.push(String::new());
+        app.command_output.extend(hook_output);
+    }
     if all_success {
         app.refresh_worktree_status();
         app.error_message = None;
@@ -700,11 +1565,65 @@ fn handle_push_project(app: &mut App, p_idx: usize, commit_msg: Option<String>)
         app.error_message = Some("Some pushes failed (see output, Ctrl+L to export)".to_string());
         app.full_error_detail = Some(app.command_output.join("\n"));
     }
+    run_hooks_then(app, HookPoint::PostPush, pushed_worktrees, AfterHooks::Report);
+}
+
+/// Opens the project folder or worktree of `sel` in the editor: GUI editors start in the
+/// background, terminal editors take over the terminal while workman is suspended.
+fn open_in_editor(app: &mut App, sel: Selection) -> AppState {
+    let Some(path) = app.session_path(sel) else {
+        return AppState::Continue;
+    };
+    let settings = &app.config.settings;
+    let Some(command) = editor::resolve(settings.editor.as_deref()) else {
+        app.error_message = Some("No editor set: choose one in Options (o) or set $VISUAL / $EDITOR.".to_string());
+        return AppState::Continue;
+    };
+    let cmd = editor::build(&command, &path);
+    if !editor::is_gui(&command, settings.editor_gui) {
+        return AppState::Suspend(cmd);
+    }
+    if let Err(e) = editor::spawn_detached(cmd) {
+        app.error_message = Some(format!("Failed to start editor: {}", e));
+    }
+    AppState::Continue
 }
 
-/// Sanitizes a string for use as a tmux session name.
-fn sanitize_tmux_name(s: &str) -> String {
-    s.chars()
-        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '-' })
-        .collect()
+/// Runs the palette's `idx`th command on the selected row. A terminal command on a worktree
+/// attaches to its new tab; on a project the tabs are left for the session switcher.
+fn run_palette_command(app: &mut App, idx: usize) {
+    let (Some(sel), Some(command)) = (app.get_selected_selection(), app.palette_commands.get(idx)) else {
+        return;
+    };
+    let name = command.name.clone();
+    app.input_mode = InputMode::Normal;
+    match app.run_user_command(sel, &name) {
+        Ok(0) => {}
+        Ok(_) if matches!(sel, Selection::Worktree(_, _)) => app.input_mode = InputMode::Terminal,
+        Ok(opened) => {
+            app.error_message = Some(format!("Started '{}' in {} terminal tabs; (s)essions to switch.", name, opened));
+        }
+        Err(e) => app.error_message = Some(e.to_string()),
+    }
+}
+
+/// Opens the multiplexer session for `sel`. From inside the multiplexer it is opened in
+/// place and workman keeps running; otherwise workman is suspended while it is attached.
+fn open_mux_session(app: &mut App, sel: Selection) -> AppState {
+    let multiplexer = app.config.settings.multiplexer();
+    let (Some(path), Some(session_name)) = (app.session_path(sel), app.mux_session_name(sel)) else {
+        return AppState::Continue;
+    };
+    if !multiplexer.inside() {
+        return AppState::Suspend(multiplexer.attach_command(&session_name, &path));
+    }
+    match multiplexer.open(&session_name, &path) {
+        Ok(()) => {
+            app.mux_sessions.insert(session_name);
+        }
+        Err(e) => {
+            app.error_message = Some(format!("Failed to open {} session: {}", multiplexer.label(), e));
+        }
+    }
+    AppState::Continue
 }
@@ -0,0 +1,132 @@
+use crate::models::Repo;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Lifecycle points at which user hooks run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HookPoint {
+    PreCreate,
+    PostCreate,
+    PrePush,
+    PostPush,
+    PreRemove,
+}
+
+impl HookPoint {
+    pub fn name(self) -> &'static str {
+        match self {
+            HookPoint::PreCreate => "pre_create",
+            HookPoint::PostCreate => "post_create",
+            HookPoint::PrePush => "pre_push",
+            HookPoint::PostPush => "post_push",
+            HookPoint::PreRemove => "pre_remove",
+        }
+    }
+}
+
+/// Shell commands to run at each lifecycle point. Used both globally (in `Settings`)
+/// and per repo (in `.workman.toml` under `[hooks]`).
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Hooks {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pre_create: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_create: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pre_push: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_push: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pre_remove: Vec<String>,
+}
+
+impl Hooks {
+    pub fn commands(&self, point: HookPoint) -> &[String] {
+        match point {
+            HookPoint::PreCreate => &self.pre_create,
+            HookPoint::PostCreate => &self.post_create,
+            HookPoint::PrePush => &self.pre_push,
+            HookPoint::PostPush => &self.post_push,
+            HookPoint::PreRemove => &self.pre_remove,
+        }
+    }
+}
+
+/// Global hooks run first, then the repo's own.
+pub fn commands_for(point: HookPoint, global: &Hooks, repo: &Hooks) -> Vec<String> {
+    global.commands(point).iter().chain(repo.commands(point)).cloned().collect()
+}
+
+/// Describes the worktree a hook or user command runs for; exported as `WORKMAN_*` variables.
+pub struct HookContext<'a> {
+    pub project: &'a str,
+    pub repo: &'a str,
+    pub branch: &'a str,
+    pub worktree: &'a Path,
+}
+
+impl HookContext<'_> {
+    pub fn env(&self, point: HookPoint) -> Vec<(String, String)> {
+        let mut env = vec![("WORKMAN_HOOK".to_string(), point.name().to_string())];
+        env.extend(self.vars());
+        env
+    }
+
+    /// The variables without `WORKMAN_HOOK`, for user commands.
+    pub fn vars(&self) -> Vec<(String, String)> {
+        vec![
+            ("WORKMAN_PROJECT".to_string(), self.project.to_string()),
+            ("WORKMAN_REPO".to_string(), self.repo.to_string()),
+            ("WORKMAN_BRANCH".to_string(), self.branch.to_string()),
+            ("WORKMAN_WORKTREE".to_string(), self.worktree.to_string_lossy().to_string()),
+        ]
+    }
+}
+
+/// The action waiting on a hook run, resumed once `tasks::spawn_hooks` reports back.
+/// Projects and worktrees are named by path, as the tree may change while hooks run.
+#[derive(Debug)]
+pub enum AfterHooks {
+    /// Create worktrees of these repos (on the given base) in the project at `project`.
+    /// For a template, `template` counts its repos that already failed to resolve.
+    Create { project: PathBuf, repos: Vec<(Repo, Option<String>)>, template: Option<usize> },
+    PushWorktree { worktree: PathBuf, commit_msg: Option<String> },
+    PushProject { project: PathBuf, commit_msg: Option<String> },
+    RemoveWorktree { worktree: PathBuf },
+    RemoveProject { project: PathBuf },
+    /// Nothing left to do (post hooks); failures are only reported.
+    Report,
+}
+
+impl AfterHooks {
+    /// Whether one worktree's failing hook cancels the whole action, so the remaining
+    /// worktrees' hooks need not run.
+    pub fn all_or_nothing(&self) -> bool {
+        matches!(self, AfterHooks::RemoveProject { .. })
+    }
+
+    /// The tree row (project folder or worktree path) the held-up action works on; it
+    /// is busy until the hooks finish.
+    pub fn row(&self) -> Option<&Path> {
+        match self {
+            AfterHooks::Create { project, .. }
+            | AfterHooks::PushProject { project, .. }
+            | AfterHooks::RemoveProject { project } => Some(project),
+            AfterHooks::PushWorktree { worktree, .. } | AfterHooks::RemoveWorktree { worktree } => Some(worktree),
+            AfterHooks::Report => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_commands_for_orders_global_first() {
+        let global = Hooks { pre_push: vec!["lint".to_string()], ..Default::default() };
+        let repo = Hooks { pre_push: vec!["test".to_string()], ..Default::default() };
+        assert_eq!(commands_for(HookPoint::PrePush, &global, &repo), vec!["lint", "test"]);
+        assert!(commands_for(HookPoint::PreRemove, &global, &repo).is_empty());
+    }
+}
@@ -0,0 +1,140 @@
+//! Normal-mode key bindings: each action's default key, overridden from
+//! `~/.workman.keymap.toml`.
+
+use crate::actions::Action;
+use crate::keys::KeyChord;
+use crate::shortcuts;
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+pub const KEYMAP_FILE: &str = ".workman.keymap.toml";
+
+/// Keys Normal mode keeps for itself: tree navigation, clearing output, and quitting.
+const RESERVED: &[KeyChord] = &[
+    KeyChord::new(KeyCode::Up, KeyModifiers::NONE),
+    KeyChord::new(KeyCode::Down, KeyModifiers::NONE),
+    KeyChord::new(KeyCode::Esc, KeyModifiers::NONE),
+    KeyChord::new(KeyCode::Char('c'), KeyModifiers::CONTROL),
+];
+
+/// The key bound to each action; `None` if the keymap file unbinds it.
+///
+/// ```toml
+/// # action = "key", in the syntax of the detach key; "" unbinds
+/// terminal = "enter"
+/// toggle_expand = "space"
+/// new_tab = "ctrl+t"
+/// quit = ""
+/// ```
+#[derive(Clone, Debug)]
+pub struct Keymap {
+    bindings: Vec<(Action, Option<KeyChord>)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self { bindings: Action::ALL.iter().map(|&a| (a, Some(a.default_key()))).collect() }
+    }
+}
+
+impl Keymap {
+    pub fn get_path() -> PathBuf {
+        dirs::home_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join(KEYMAP_FILE)
+    }
+
+    /// Loads the keymap file, if there is one. An invalid file is ignored in favour of the
+    /// defaults, with a notice saying why.
+    pub fn load() -> (Self, Option<String>) {
+        let path = Self::get_path();
+        let Ok(content) = fs::read_to_string(&path) else {
+            return (Self::default(), None);
+        };
+        match Self::from_toml(&content) {
+            Ok(keymap) => (keymap, None),
+            Err(e) => (Self::default(), Some(format!("Ignoring ~/{}: {}", KEYMAP_FILE, e))),
+        }
+    }
+
+    /// The defaults with the bindings in `content` applied. Every key may be bound once.
+    pub fn from_toml(content: &str) -> Result<Self> {
+        let overrides: BTreeMap<String, String> = toml::from_str(content)?;
+        let mut keymap = Self::default();
+        for (name, key) in overrides {
+            let action = Action::parse(&name).ok_or_else(|| anyhow::anyhow!("unknown action '{}'", name))?;
+            let chord = match key.trim() {
+                "" => None,
+                key => Some(KeyChord::parse(key).map_err(|e| anyhow::anyhow!("{}: {}", name, e))?),
+            };
+            if let Some(chord) = chord
+                && RESERVED.contains(&chord)
+            {
+                return Err(anyhow::anyhow!("{} is reserved and can't be bound to {}", chord, name));
+            }
+            keymap.set(action, chord);
+        }
+        if let Some(chord) = shortcuts::find_collision(keymap.bindings.iter().filter_map(|(_, k)| *k)) {
+            let names: Vec<&str> = keymap.bindings.iter()
+                .filter(|(_, k)| *k == Some(chord))
+                .map(|(a, _)| a.name())
+                .collect();
+            return Err(anyhow::anyhow!("{} is bound to both {}", chord, names.join(" and ")));
+        }
+        Ok(keymap)
+    }
+
+    fn set(&mut self, action: Action, chord: Option<KeyChord>) {
+        if let Some(binding) = self.bindings.iter_mut().find(|(a, _)| *a == action) {
+            binding.1 = chord;
+        }
+    }
+
+    pub fn key(&self, action: Action) -> Option<KeyChord> {
+        self.bindings.iter().find(|(a, _)| *a == action).and_then(|(_, k)| *k)
+    }
+
+    /// The action `key` is bound to. Letters are case-sensitive here, so `t` and `T` can
+    /// do different things.
+    pub fn action_for(&self, key: &KeyEvent) -> Option<Action> {
+        let chord = KeyChord::from_event(key);
+        self.bindings.iter().find(|(_, k)| *k == Some(chord)).map(|(a, _)| *a)
+    }
+
+    /// The action's key as shown in the help bar, help screen and palette; empty if unbound.
+    pub fn hint(&self, action: Action) -> String {
+        self.key(action).map(|k| k.to_string()).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overrides_and_unbinding() {
+        let keymap = Keymap::from_toml("terminal = \"enter\"\ntoggle_expand = \"space\"\nquit = \"\"\n").unwrap();
+        assert_eq!(keymap.action_for(&KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE)), Some(Action::Terminal));
+        assert_eq!(keymap.action_for(&KeyEvent::new(KeyCode::Char(' '), KeyModifiers::NONE)), Some(Action::ToggleExpand));
+        assert_eq!(keymap.action_for(&KeyEvent::new(KeyCode::Char('t'), KeyModifiers::NONE)), None);
+        assert_eq!(keymap.key(Action::Quit), None);
+        assert_eq!(keymap.hint(Action::Terminal), "Enter");
+        // Untouched actions keep their defaults; t and T stay distinct
+        assert_eq!(keymap.action_for(&KeyEvent::new(KeyCode::Char('T'), KeyModifiers::SHIFT)), Some(Action::NewTab));
+    }
+
+    #[test]
+    fn test_invalid_keymaps_are_rejected() {
+        let err = Keymap::from_toml("push = \"t\"\n").unwrap_err();
+        assert!(err.to_string().contains("bound to both"), "{}", err);
+        // Moving the other action away resolves it
+        assert!(Keymap::from_toml("push = \"t\"\nterminal = \"enter\"\ntoggle_expand = \"\"\n").is_ok());
+
+        assert!(Keymap::from_toml("teleport = \"t\"\n").unwrap_err().to_string().contains("unknown action"));
+        assert!(Keymap::from_toml("push = \"hyper+p\"\n").is_err());
+        assert!(Keymap::from_toml("help = \"esc\"\n").unwrap_err().to_string().contains("reserved"));
+    }
+}
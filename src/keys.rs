@@ -0,0 +1,203 @@
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::fmt;
+
+/// A single key plus modifiers, written in config as e.g. `ctrl+]`, `alt+d`, `f12` or `esc`.
+/// Letters are stored lowercase; an uppercase letter (`T`) is the same as `shift+t`. Shift
+/// on other characters is dropped, as events never carry it (`shift+/` is `/`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    pub const fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self { code, modifiers }
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err(anyhow::anyhow!("Key cannot be empty."));
+        }
+        // Split on '+' but keep a trailing literal '+' (e.g. "ctrl++")
+        let (mods, key) = match text.strip_suffix("++") {
+            Some(rest) => (rest, "+"),
+            None => match text.rsplit_once('+') {
+                Some((mods, key)) => (mods, key),
+                None => ("", text),
+            },
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        for m in mods.split('+').filter(|m| !m.is_empty()) {
+            modifiers |= match m.to_lowercase().as_str() {
+                "ctrl" | "control" | "c" => KeyModifiers::CONTROL,
+                "alt" | "meta" | "m" => KeyModifiers::ALT,
+                "shift" | "s" => KeyModifiers::SHIFT,
+                other => return Err(anyhow::anyhow!("Unknown modifier '{}'.", other)),
+            };
+        }
+
+        let lower = key.to_lowercase();
+        let code = match lower.as_str() {
+            "esc" | "escape" => KeyCode::Esc,
+            "enter" | "return" => KeyCode::Enter,
+            "tab" => KeyCode::Tab,
+            "backspace" => KeyCode::Backspace,
+            "space" => KeyCode::Char(' '),
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "insert" => KeyCode::Insert,
+            "delete" | "del" => KeyCode::Delete,
+            f if f.len() > 1 && f.starts_with('f') && f[1..].parse::<u8>().is_ok_and(|n| (1..=12).contains(&n)) => {
+                KeyCode::F(f[1..].parse().unwrap())
+            }
+            _ => {
+                let mut chars = key.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => {
+                        if c.is_ascii_uppercase() {
+                            modifiers |= KeyModifiers::SHIFT;
+                        }
+                        KeyCode::Char(c.to_ascii_lowercase())
+                    }
+                    _ => return Err(anyhow::anyhow!("Unknown key '{}'.", key)),
+                }
+            }
+        };
+        // Same rule as `from_event`, or the chord could never match
+        if let KeyCode::Char(c) = code
+            && !c.is_ascii_alphabetic()
+        {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        Ok(Self { code, modifiers })
+    }
+
+    /// Normalizes a crossterm event so it compares equal to a parsed chord. Letters are
+    /// lowercased with an uppercase one keeping shift; other characters drop shift (it is
+    /// implied by the character). The legacy control bytes crossterm reports as
+    /// `Ctrl+4`..`Ctrl+7` are mapped back to `\ ] ^ _`.
+    pub fn from_event(key: &KeyEvent) -> Self {
+        let mut modifiers = key.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        let code = match key.code {
+            KeyCode::Char(c) => {
+                if c.is_ascii_uppercase() {
+                    modifiers |= KeyModifiers::SHIFT;
+                } else if !c.is_ascii_alphabetic() {
+                    modifiers.remove(KeyModifiers::SHIFT);
+                }
+                let c = if modifiers.contains(KeyModifiers::CONTROL) {
+                    match c {
+                        '4' => '\\',
+                        '5' => ']',
+                        '6' => '^',
+                        '7' => '_',
+                        c => c,
+                    }
+                } else {
+                    c
+                };
+                KeyCode::Char(c.to_ascii_lowercase())
+            }
+            other => other,
+        };
+        Self { code, modifiers }
+    }
+
+    /// Whether `key` is this chord, ignoring the case of letters (`alt+d` matches Alt+D).
+    pub fn matches(&self, key: &KeyEvent) -> bool {
+        let mut expected = *self;
+        let mut actual = Self::from_event(key);
+        if matches!(expected.code, KeyCode::Char(_)) {
+            expected.modifiers.remove(KeyModifiers::SHIFT);
+            actual.modifiers.remove(KeyModifiers::SHIFT);
+        }
+        actual == expected
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt+")?;
+        }
+        let shifted_letter = matches!(self.code, KeyCode::Char(c) if c.is_ascii_alphabetic())
+            && self.modifiers.contains(KeyModifiers::SHIFT);
+        if self.modifiers.contains(KeyModifiers::SHIFT) && !shifted_letter {
+            write!(f, "Shift+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) if shifted_letter => write!(f, "{}", c.to_ascii_uppercase()),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            KeyCode::Esc => write!(f, "Esc"),
+            KeyCode::PageUp => write!(f, "PageUp"),
+            KeyCode::PageDown => write!(f, "PageDown"),
+            other => write!(f, "{:?}", other),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_display() {
+        let k = KeyChord::parse("ctrl+]").unwrap();
+        assert_eq!(k, KeyChord::new(KeyCode::Char(']'), KeyModifiers::CONTROL));
+        assert_eq!(k.to_string(), "Ctrl+]");
+        assert_eq!(KeyChord::parse("Alt+Shift+F5").unwrap().to_string(), "Alt+Shift+F5");
+        assert_eq!(KeyChord::parse("ctrl++").unwrap().code, KeyCode::Char('+'));
+        assert_eq!(KeyChord::parse("esc").unwrap().code, KeyCode::Esc);
+        assert!(KeyChord::parse("hyper+x").is_err());
+        assert!(KeyChord::parse("ctrl+nope").is_err());
+    }
+
+    #[test]
+    fn test_matches_crossterm_events() {
+        let detach = KeyChord::parse("ctrl+]").unwrap();
+        // crossterm decodes the 0x1d byte as Ctrl+5
+        assert!(detach.matches(&KeyEvent::new(KeyCode::Char('5'), KeyModifiers::CONTROL)));
+        assert!(!detach.matches(&KeyEvent::new(KeyCode::Char(']'), KeyModifiers::NONE)));
+
+        let alt_d = KeyChord::parse("alt+d").unwrap();
+        assert!(alt_d.matches(&KeyEvent::new(KeyCode::Char('D'), KeyModifiers::ALT | KeyModifiers::SHIFT)));
+    }
+
+    #[test]
+    fn test_uppercase_letters_are_shifted() {
+        let upper_t = KeyChord::parse("T").unwrap();
+        assert_eq!(upper_t, KeyChord::parse("shift+t").unwrap());
+        assert_eq!(upper_t.to_string(), "T");
+        assert_eq!(KeyChord::parse(&upper_t.to_string()).unwrap(), upper_t);
+
+        // Events compare exactly, so t and T can be told apart
+        assert_eq!(KeyChord::from_event(&KeyEvent::new(KeyCode::Char('T'), KeyModifiers::SHIFT)), upper_t);
+        assert_eq!(KeyChord::from_event(&KeyEvent::new(KeyCode::Char('T'), KeyModifiers::NONE)), upper_t);
+        assert_ne!(KeyChord::from_event(&KeyEvent::new(KeyCode::Char('t'), KeyModifiers::NONE)), upper_t);
+        // Shift is implied by symbols, whether in an event or a parsed chord
+        assert_eq!(
+            KeyChord::from_event(&KeyEvent::new(KeyCode::Char('|'), KeyModifiers::SHIFT)),
+            KeyChord::parse("|").unwrap()
+        );
+        assert_eq!(KeyChord::parse("shift+/").unwrap(), KeyChord::parse("/").unwrap());
+        assert_eq!(
+            KeyChord::from_event(&KeyEvent::new(KeyCode::Char('1'), KeyModifiers::SHIFT)),
+            KeyChord::parse("shift+1").unwrap()
+        );
+    }
+}
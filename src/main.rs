@@ -1,14 +1,29 @@
+mod actions;
 mod app;
+mod branch_naming;
+mod commands;
+mod daemon;
+mod editor;
 mod event_handler;
+mod hooks;
+mod keymap;
+mod keys;
 mod models;
+mod multiplexer;
+mod panes;
+mod repo_config;
 mod session;
 mod shortcuts;
+mod tasks;
 mod terminal_handler;
 mod ui;
 
 use anyhow::Result;
 use crossterm::{
-    event::{self, Event, KeyEventKind},
+    event::{
+        DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture, Event,
+        EventStream, KeyEventKind,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -16,9 +31,12 @@ use ratatui::{
     backend::{Backend, CrosstermBackend},
     Terminal,
 };
-use std::{io, sync::atomic::{AtomicBool, Ordering}, sync::Arc};
+use futures::StreamExt;
+use std::{io, time::Duration};
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+use tokio::time::MissedTickBehavior;
 
-use crate::app::App;
+use crate::app::{App, InputMode};
 use crate::ui::ui;
 
 struct TerminalRestorer;
@@ -27,35 +45,42 @@ impl Drop for TerminalRestorer {
     fn drop(&mut self) {
         let _ = disable_raw_mode();
         let mut stdout = io::stdout();
-        let _ = execute!(stdout, LeaveAlternateScreen);
+        let _ = execute!(stdout, DisableMouseCapture, DisableBracketedPaste, LeaveAlternateScreen);
     }
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    if std::env::args().nth(1).as_deref() == Some(daemon::DAEMON_FLAG) {
+        return daemon::run();
+    }
+
     let _term_restorer = TerminalRestorer;
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen)?;
+    execute!(stdout, EnterAlternateScreen, EnableBracketedPaste, EnableMouseCapture)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let running = Arc::new(AtomicBool::new(true));
-    let r = running.clone();
-
-    let mut signals = signal_hook::iterator::Signals::new(&[
+    // Signals are forwarded to the event loop, which exits cleanly so the terminal is restored
+    let (signal_tx, signal_rx) = mpsc::unbounded_channel();
+    let mut signals = signal_hook::iterator::Signals::new([
         signal_hook::consts::SIGTERM,
         signal_hook::consts::SIGINT,
     ])?;
     std::thread::spawn(move || {
-        for _ in signals.forever() {
-            r.store(false, Ordering::SeqCst);
-            break;
+        for signal in signals.forever() {
+            if signal_tx.send(signal).is_err() {
+                break;
+            }
         }
     });
 
-    let app = App::new();
-    let res = run_app(&mut terminal, app, running).await;
+    let mut app = App::new();
+    let res = run_app(&mut terminal, &mut app, signal_rx).await;
+    // Persistent sessions keep running for the next start; the rest end with the app
+    app.detach_sessions();
+    drop(app);
 
     if let Err(err) = res {
         println!("{:?}", err)
@@ -64,51 +89,96 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+/// How often exited shells are reaped and time-based displays (idle times) refreshed
+/// when nothing else happens.
+const HOUSEKEEPING_INTERVAL: Duration = Duration::from_secs(1);
+/// How often worktree statuses are re-read, to pick up edits made in terminals.
+const STATUS_REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Waits for input, session output, background task events, timers or signals, and
+/// redraws only after something changed.
 async fn run_app<B: Backend + io::Write>(
     terminal: &mut Terminal<B>,
-    mut app: App,
-    running: Arc<AtomicBool>,
+    app: &mut App,
+    mut signals: UnboundedReceiver<i32>,
 ) -> Result<()> {
-    while running.load(Ordering::SeqCst) {
-        let terminal_area = terminal.get_frame().area();
-        let current_width = terminal_area.width;
-        let current_height = terminal_area.height;
-
-        // Resize active PTY session if terminal dimensions changed
-        if let Some(sel) = app.get_selected_selection() {
-            if let Some(session) = app.sessions.get_mut(&sel) {
-                let _ = session.resize(current_width, current_height);
-            }
+    let mut events = EventStream::new();
+    let mut housekeeping = tokio::time::interval(HOUSEKEEPING_INTERVAL);
+    let mut status_refresh = tokio::time::interval(STATUS_REFRESH_INTERVAL);
+    housekeeping.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    status_refresh.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    status_refresh.reset(); // statuses were just computed by App::new
+    let mut dirty = true;
+
+    loop {
+        if dirty {
+            // Output arriving from here on wakes the loop again
+            app.output_notifier.clear();
+            terminal.draw(|f| ui(f, app)).map_err(|e| anyhow::anyhow!(e.to_string()))?;
+            app.resize_panes();
+            dirty = false;
         }
 
-        terminal.draw(|f| ui(f, &mut app)).map_err(|e| anyhow::anyhow!(e.to_string()))?;
-
-        if event::poll(std::time::Duration::from_millis(50))? {
-            if let Event::Key(key) = event::read()? {
-                if key.kind == KeyEventKind::Press {
-                    match event_handler::handle_key_event(key, &mut app, current_width, current_height).await? {
-                        event_handler::AppState::Quit => return Ok(()),
-                        event_handler::AppState::Continue => {}
-                        event_handler::AppState::TmuxSession { path, session_name } => {
-                            // Suspend workman: restore normal terminal mode
-                            disable_raw_mode()?;
-                            execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
-
-                            // Hand off to tmux (-A: attach if exists, else create)
-                            let _ = std::process::Command::new("tmux")
-                                .args(["new-session", "-A", "-s", &session_name, "-c"])
-                                .arg(&path)
-                                .status();
-
-                            // Resume workman
-                            enable_raw_mode()?;
-                            execute!(terminal.backend_mut(), EnterAlternateScreen)?;
-                            let _ = terminal.clear();
-                        }
-                    }
+        let event = tokio::select! {
+            event = events.next() => match event {
+                Some(event) => event?,
+                None => return Ok(()),
+            },
+            Some(task_event) = app.task_rx.recv() => {
+                event_handler::handle_task_event(task_event, app);
+                while let Ok(task_event) = app.task_rx.try_recv() {
+                    event_handler::handle_task_event(task_event, app);
+                }
+                dirty = true;
+                continue;
+            }
+            _ = housekeeping.tick() => {
+                // Idle times in the session switcher move on even without output
+                dirty = app.reap_sessions() || app.input_mode == InputMode::Sessions;
+                continue;
+            }
+            _ = status_refresh.tick() => {
+                app.spawn_status_refresh();
+                continue;
+            }
+            _ = signals.recv() => return Ok(()),
+        };
+
+        dirty = true;
+        let key = match event {
+            Event::Key(key) if key.kind == KeyEventKind::Press => key,
+            Event::Paste(text) => {
+                event_handler::handle_paste_event(text, app);
+                continue;
+            }
+            Event::Mouse(mouse) => {
+                event_handler::handle_mouse_event(mouse, app);
+                continue;
+            }
+            // Redraw right away; the new layout resizes the sessions to their panes
+            Event::Resize(_, _) => continue,
+            _ => continue,
+        };
+        match event_handler::handle_key_event(key, app).await? {
+            event_handler::AppState::Quit => return Ok(()),
+            event_handler::AppState::Continue => {}
+            event_handler::AppState::Suspend(mut command) => {
+                // Suspend workman: restore normal terminal mode, and stop reading input
+                // (replacing the stream drops its reader) so keys reach the program
+                events = EventStream::new();
+                disable_raw_mode()?;
+                execute!(terminal.backend_mut(), DisableMouseCapture, DisableBracketedPaste, LeaveAlternateScreen)?;
+
+                if let Err(e) = command.status() {
+                    app.error_message = Some(format!("Failed to run {:?}: {}", command.get_program(), e));
                 }
+
+                // Resume workman
+                enable_raw_mode()?;
+                execute!(terminal.backend_mut(), EnterAlternateScreen, EnableBracketedPaste, EnableMouseCapture)?;
+                let _ = terminal.clear();
+                app.spawn_status_refresh();
             }
         }
     }
-    Ok(())
 }
@@ -1,6 +1,29 @@
+use crate::commands::UserCommand;
+use crate::hooks::Hooks;
+use crate::keys::KeyChord;
+use crate::multiplexer::Multiplexer;
+use crossterm::event::{KeyCode, KeyModifiers};
+use crate::branch_naming::BranchNaming;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::{fs, path::PathBuf};
+use std::{fs, path::{Path, PathBuf}};
+
+/// Default worktree location: a hidden `.workman/` folder inside the repo.
+pub const DEFAULT_WORKTREE_LOCATION: &str = "{repo_path}/.workman/{branch}";
+
+/// Checks that a worktree location template gives every repo and branch its own folder.
+pub fn validate_worktree_location(location: &str) -> Result<()> {
+    if !location.contains("{branch}") {
+        anyhow::bail!("Location must contain {{branch}} so each branch gets its own folder.");
+    }
+    if !location.contains("{repo}") && !location.contains("{repo_path}") {
+        anyhow::bail!("Location must contain {{repo}} or {{repo_path}} so repos sharing a branch don't collide.");
+    }
+    Ok(())
+}
+
+/// Leaves the embedded terminal. Esc itself is forwarded to the shell so vim and fzf work.
+pub const DEFAULT_DETACH_KEY: KeyChord = KeyChord::new(KeyCode::Char(']'), KeyModifiers::CONTROL);
 
 /// A registered git repository in the global pool.
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -10,33 +33,109 @@ pub struct Repo {
 }
 
 impl Repo {
-    /// Sanitizes a branch name for use as a filesystem directory name.
+    /// Encodes a branch name as a single filesystem directory name.
+    ///
+    /// `/` becomes `+`; literal `%` and `+` are escaped as `%25` and `%2B`. The mapping is
+    /// reversible, so distinct branches (e.g. `feat/a-b` and `feat-a/b`) never share a folder.
     pub fn sanitize_branch(branch: &str) -> String {
-        branch.replace('/', "-")
+        let mut out = String::with_capacity(branch.len());
+        for c in branch.chars() {
+            match c {
+                '%' => out.push_str("%25"),
+                '+' => out.push_str("%2B"),
+                '/' => out.push('+'),
+                c => out.push(c),
+            }
+        }
+        out
+    }
+
+    /// Returns where the worktree for `branch` is (or would be) checked out, expanding a
+    /// location template. Supports `{repo}`, `{repo_path}`, `{branch}` and a leading `~`;
+    /// relative results are resolved against the repo root.
+    pub fn worktree_path(&self, branch: &str, location: &str) -> PathBuf {
+        let expanded = location
+            .replace("{repo_path}", &self.path.to_string_lossy())
+            .replace("{repo}", &self.name)
+            .replace("{branch}", &Self::sanitize_branch(branch));
+        let path = match expanded.strip_prefix("~/") {
+            Some(rest) => dirs::home_dir().unwrap_or_else(|| PathBuf::from(".")).join(rest),
+            None => PathBuf::from(expanded),
+        };
+        self.path.join(path)
     }
 
-    /// Creates a git worktree for this repo on the given branch.
-    /// Returns the git command output and the worktree path.
-    pub fn add_worktree(&self, branch: &str) -> Result<(std::process::Output, PathBuf)> {
-        let workman_dir = self.path.join(".workman");
-        if !workman_dir.exists() {
-            fs::create_dir_all(&workman_dir)?;
+    /// Hides worktrees that live inside the repo from `git status` via `.git/info/exclude`,
+    /// leaving the tracked `.gitignore` untouched.
+    fn exclude_worktree_dir(&self, wt_path: &Path) -> Result<()> {
+        let Ok(relative) = wt_path.strip_prefix(&self.path) else {
+            return Ok(());
+        };
+        let Some(top) = relative.components().next() else {
+            return Ok(());
+        };
+        let pattern = format!("/{}/", top.as_os_str().to_string_lossy());
+
+        let git_path = std::process::Command::new("git")
+            .arg("-C").arg(&self.path)
+            .arg("rev-parse").arg("--git-path").arg("info/exclude")
+            .output()?;
+        if !git_path.status.success() {
+            return Err(anyhow::anyhow!("not a git repository: {:?}", self.path));
         }
+        let exclude_path = self.path.join(String::from_utf8_lossy(&git_path.stdout).trim());
 
-        let gitignore_path = self.path.join(".gitignore");
-        let mut needs_append = true;
-        if let Ok(content) = fs::read_to_string(&gitignore_path) {
-            if content.lines().any(|l| l.trim() == ".workman/" || l.trim() == ".workman") {
-                needs_append = false;
-            }
+        let content = fs::read_to_string(&exclude_path).unwrap_or_default();
+        if content.lines().any(|l| l.trim() == pattern) {
+            return Ok(());
         }
-        if needs_append {
-            use std::io::Write;
-            if let Ok(mut file) = fs::OpenOptions::new().append(true).create(true).open(&gitignore_path) {
-                let _ = writeln!(file, "\n# workman worktrees\n.workman/");
-            }
+        if let Some(parent) = exclude_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        use std::io::Write;
+        let mut file = fs::OpenOptions::new().append(true).create(true).open(&exclude_path)?;
+        let separator = if content.is_empty() || content.ends_with('\n') { "" } else { "\n" };
+        writeln!(file, "{}# workman worktrees\n{}", separator, pattern)?;
+        Ok(())
+    }
+
+    /// Fails with a descriptive error if `wt_path` is already occupied by something other than
+    /// an empty directory, so git's own "already exists" error never surfaces.
+    fn check_target_free(&self, wt_path: &Path, branch: &str) -> Result<()> {
+        let is_empty_dir = fs::read_dir(wt_path).map(|mut d| d.next().is_none()).unwrap_or(false);
+        if !wt_path.exists() || is_empty_dir {
+            return Ok(());
         }
 
+        let git_in = |dir: &Path, args: &[&str]| -> Option<String> {
+            let out = std::process::Command::new("git").arg("-C").arg(dir).args(args).output().ok()?;
+            out.status.success().then(|| String::from_utf8_lossy(&out.stdout).trim().to_string())
+        };
+        let common_dir = ["rev-parse", "--path-format=absolute", "--git-common-dir"];
+        let is_worktree = wt_path.join(".git").exists();
+
+        if is_worktree && git_in(wt_path, &common_dir) != git_in(&self.path, &common_dir) {
+            return Err(anyhow::anyhow!(
+                "{} already exists and belongs to another repository", wt_path.display()
+            ));
+        }
+        match is_worktree.then(|| git_in(wt_path, &["symbolic-ref", "--short", "HEAD"])).flatten() {
+            Some(existing) if existing == branch => Err(anyhow::anyhow!(
+                "A worktree for '{}' already exists at {}", branch, wt_path.display()
+            )),
+            Some(existing) => Err(anyhow::anyhow!(
+                "{} already exists for branch '{}'", wt_path.display(), existing
+            )),
+            None => Err(anyhow::anyhow!(
+                "{} already exists and is not a worktree for '{}'", wt_path.display(), branch
+            )),
+        }
+    }
+
+    /// Creates a git worktree for this repo on the given branch, placed according to the
+    /// `location` template. A new branch starts from `base` (default: HEAD); an existing
+    /// branch is checked out as is. Returns the git command output and the worktree path.
+    pub fn add_worktree(&self, branch: &str, location: &str, base: Option<&str>) -> Result<(std::process::Output, PathBuf)> {
         let valid_format = std::process::Command::new("git")
             .arg("-C").arg(&self.path)
             .arg("check-ref-format").arg("--normalize")
@@ -55,13 +154,20 @@ impl Repo {
             .map(|o| o.status.success())
             .unwrap_or(false);
 
-        let wt_dir_name = Self::sanitize_branch(branch);
-        let wt_path = workman_dir.join(&wt_dir_name);
+        let wt_path = self.worktree_path(branch, location);
+        self.check_target_free(&wt_path, branch)?;
+        if let Some(parent) = wt_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        self.exclude_worktree_dir(&wt_path)?;
 
         let mut cmd = std::process::Command::new("git");
         cmd.arg("-C").arg(&self.path).arg("worktree").arg("add");
         if !branch_exists {
             cmd.arg("-b").arg(branch).arg(&wt_path);
+            if let Some(base) = base {
+                cmd.arg(base);
+            }
         } else {
             cmd.arg(&wt_path).arg(branch);
         }
@@ -128,15 +234,15 @@ impl ProjectWorktree {
             for line in stdout.lines() {
                 let parts: Vec<&str> = line.split('\t').collect();
                 if parts.len() == 3 {
-                    if parts[0] != "-" {
-                        if let Ok(added) = parts[0].parse::<i32>() {
-                            total_insertions += added;
-                        }
+                    if parts[0] != "-"
+                        && let Ok(added) = parts[0].parse::<i32>()
+                    {
+                        total_insertions += added;
                     }
-                    if parts[1] != "-" {
-                        if let Ok(deleted) = parts[1].parse::<i32>() {
-                            total_deletions += deleted;
-                        }
+                    if parts[1] != "-"
+                        && let Ok(deleted) = parts[1].parse::<i32>()
+                    {
+                        total_deletions += deleted;
                     }
                 }
             }
@@ -175,6 +281,16 @@ impl ProjectWorktree {
     }
 }
 
+/// Whether a `get_status` summary shows uncommitted, untracked or unpushed changes.
+pub fn status_has_changes(status: &str) -> bool {
+    status != "clean" && status != "N/A"
+}
+
+/// Whether a `get_status` summary shows commits not pushed upstream.
+pub fn status_has_unpushed(status: &str) -> bool {
+    status.contains('↑')
+}
+
 /// A named project grouping worktrees across multiple repos, all on the same branch.
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Project {
@@ -221,16 +337,88 @@ impl Project {
     }
 }
 
-/// Global application settings.
+/// A repo entry in a project template.
 #[derive(Serialize, Deserialize, Clone, Debug)]
-pub struct Settings {
+pub struct TemplateRepo {
+    /// Name of a registered repo, or a path to one.
+    pub repo: String,
+    /// Ref new branches start from (e.g. `origin/main`); defaults to the repo's HEAD.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base: Option<String>,
+}
+
+/// A named, reusable set of repos for creating a project in one step.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ProjectTemplate {
+    pub name: String,
     #[serde(default)]
+    pub repos: Vec<TemplateRepo>,
+    /// Prepended to the branch derived from the project name (e.g. `feat/`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch_prefix: Option<String>,
+}
+
+/// Global application settings.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Settings {
+    /// Legacy switch for the tmux backend, read but no longer written; see `multiplexer()`.
+    #[serde(default, skip_serializing)]
     pub use_tmux: bool,
+    /// Terminal backend: `builtin`, `tmux`, `zellij` or `screen`. `None` uses the built-in one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub multiplexer: Option<String>,
+    /// Run terminals in the session daemon so they survive quitting workman.
+    #[serde(default)]
+    pub persistent_sessions: bool,
+    /// Global lifecycle hooks, run before each repo's own `.workman.toml` hooks.
+    #[serde(default)]
+    pub hooks: Hooks,
+    /// Commands offered by the command palette everywhere, alongside each repo's own.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub commands: Vec<UserCommand>,
+    /// Where new worktrees are placed; see `Repo::worktree_path`. `None` uses the default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worktree_location: Option<String>,
+    /// How branch names are derived from project names.
+    #[serde(default)]
+    pub branch_naming: BranchNaming,
+    /// Key that leaves the embedded terminal, e.g. `ctrl+]`. `None` uses the default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detach_key: Option<String>,
+    /// Command that opens a folder in the editor, e.g. `code {path}` or `nvim`.
+    /// `None` uses `$VISUAL`, then `$EDITOR`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub editor: Option<String>,
+    /// Whether the editor opens its own window (started in the background) rather than
+    /// running in the terminal. `None` guesses from the program name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub editor_gui: Option<bool>,
 }
 
-impl Default for Settings {
-    fn default() -> Self {
-        Settings { use_tmux: false }
+impl Settings {
+    pub fn worktree_location(&self) -> &str {
+        self.worktree_location.as_deref().unwrap_or(DEFAULT_WORKTREE_LOCATION)
+    }
+
+    /// The configured detach key; falls back to the default if unset or unparsable.
+    pub fn detach_key(&self) -> KeyChord {
+        self.detach_key.as_deref()
+            .and_then(|k| KeyChord::parse(k).ok())
+            .unwrap_or(DEFAULT_DETACH_KEY)
+    }
+
+    /// The configured terminal backend; `use_tmux` from older configs still selects tmux.
+    pub fn multiplexer(&self) -> Multiplexer {
+        match self.multiplexer.as_deref().and_then(Multiplexer::parse) {
+            Some(multiplexer) => multiplexer,
+            None if self.use_tmux => Multiplexer::Tmux,
+            None => Multiplexer::Builtin,
+        }
+    }
+
+    pub fn set_multiplexer(&mut self, multiplexer: Multiplexer) {
+        self.multiplexer = multiplexer.is_external().then(|| multiplexer.name().to_string());
+        self.use_tmux = false;
     }
 }
 
@@ -242,6 +430,8 @@ pub struct Config {
     pub projects: Vec<Project>,
     #[serde(default)]
     pub settings: Settings,
+    #[serde(default)]
+    pub templates: Vec<ProjectTemplate>,
 }
 
 impl Config {
@@ -292,6 +482,7 @@ impl Config {
                 repos,
                 projects: Vec::new(),
                 settings: Settings::default(),
+                templates: Vec::new(),
             };
             let _ = new_config.save();
             return (new_config, Some(
@@ -355,13 +546,191 @@ mod tests {
         assert_eq!(decoded.projects[0].branch, "feat/my-feature");
         assert_eq!(decoded.projects[0].worktrees.len(), 1);
         assert_eq!(decoded.projects[0].worktrees[0].repo_name, "myrepo");
+        assert!(decoded.templates.is_empty());
+    }
+
+    #[test]
+    fn test_template_deserialization() {
+        let json = r#"{
+            "repos": [],
+            "templates": [{
+                "name": "fullstack",
+                "branch_prefix": "feat/",
+                "repos": [{ "repo": "frontend" }, { "repo": "backend", "base": "origin/develop" }]
+            }]
+        }"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+        let t = &config.templates[0];
+        assert_eq!(t.name, "fullstack");
+        assert_eq!(t.branch_prefix.as_deref(), Some("feat/"));
+        assert_eq!(t.repos.len(), 2);
+        assert_eq!(t.repos[0].base, None);
+        assert_eq!(t.repos[1].base.as_deref(), Some("origin/develop"));
+    }
+
+    #[test]
+    fn test_add_worktree_from_base_ref() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_path = fs::canonicalize(temp_dir.path()).unwrap();
+        init_repo(&repo_path);
+        let repo = Repo { name: "r".to_string(), path: repo_path.clone() };
+        let rev = |dir: &Path, r: &str| {
+            let out = std::process::Command::new("git").arg("-C").arg(dir).args(["rev-parse", r]).output().unwrap();
+            String::from_utf8_lossy(&out.stdout).trim().to_string()
+        };
+        let first = rev(&repo_path, "HEAD");
+        let out = std::process::Command::new("git")
+            .arg("-C").arg(&repo_path)
+            .args(["-c", "user.name=w", "-c", "user.email=w@example.com", "commit", "-q", "--allow-empty", "-m", "second"])
+            .output().unwrap();
+        assert!(out.status.success());
+
+        let (out, wt_path) = repo.add_worktree("feat/base", DEFAULT_WORKTREE_LOCATION, Some(&first)).unwrap();
+        assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+        assert_eq!(rev(&wt_path, "HEAD"), first);
     }
 
     #[test]
     fn test_sanitize_branch() {
-        assert_eq!(Repo::sanitize_branch("feat/my-feature"), "feat-my-feature");
+        assert_eq!(Repo::sanitize_branch("feat/my-feature"), "feat+my-feature");
         assert_eq!(Repo::sanitize_branch("main"), "main");
-        assert_eq!(Repo::sanitize_branch("fix/bug/nested"), "fix-bug-nested");
+        assert_eq!(Repo::sanitize_branch("fix/bug/nested"), "fix+bug+nested");
+        assert_eq!(Repo::sanitize_branch("c++/x"), "c%2B%2B+x");
+        assert_eq!(Repo::sanitize_branch("100%"), "100%25");
+    }
+
+    /// Inverse of `Repo::sanitize_branch`, used to prove the encoding loses nothing.
+    fn decode_branch(dir: &str) -> String {
+        dir.replace('+', "/").replace("%2B", "+").replace("%25", "%")
+    }
+
+    #[test]
+    fn test_sanitize_branch_collision_free() {
+        let branches = [
+            "feat/a-b", "feat-a/b", "feat-a-b", "feat/a/b",
+            "a+b", "a/b", "a%2Fb", "a%2B", "a+", "a/", "%25", "%",
+        ];
+        let encoded: std::collections::HashSet<String> =
+            branches.iter().map(|b| Repo::sanitize_branch(b)).collect();
+        assert_eq!(encoded.len(), branches.len());
+        for b in branches {
+            assert_eq!(decode_branch(&Repo::sanitize_branch(b)), b);
+            assert!(!Repo::sanitize_branch(b).contains('/'));
+        }
+    }
+
+    /// Creates a git repo with one empty commit so worktrees can be added.
+    fn init_repo(path: &Path) {
+        let git = |args: &[&str]| {
+            let out = std::process::Command::new("git")
+                .arg("-C").arg(path)
+                .args(["-c", "user.name=workman", "-c", "user.email=workman@example.com"])
+                .args(args)
+                .output().unwrap();
+            assert!(out.status.success(), "git {:?} failed: {}", args, String::from_utf8_lossy(&out.stderr));
+        };
+        git(&["init", "-q"]);
+        git(&["commit", "-q", "--allow-empty", "-m", "init"]);
+    }
+
+    #[test]
+    fn test_worktree_path_templates() {
+        let repo = Repo { name: "api".to_string(), path: PathBuf::from("/src/api") };
+        assert_eq!(
+            repo.worktree_path("feat/x", DEFAULT_WORKTREE_LOCATION),
+            repo.path.join(".workman").join(Repo::sanitize_branch("feat/x"))
+        );
+        assert_eq!(
+            repo.worktree_path("main", "/wt/{repo}/{branch}"),
+            PathBuf::from("/wt/api/main")
+        );
+        assert_eq!(
+            repo.worktree_path("main", "~/wt/{repo}/{branch}"),
+            dirs::home_dir().unwrap().join("wt/api/main")
+        );
+        // Relative templates resolve against the repo root
+        assert_eq!(repo.worktree_path("main", "trees/{branch}"), PathBuf::from("/src/api/trees/main"));
+    }
+
+    #[test]
+    fn test_validate_worktree_location() {
+        assert!(validate_worktree_location(DEFAULT_WORKTREE_LOCATION).is_ok());
+        assert!(validate_worktree_location("~/wt/{repo}/{branch}").is_ok());
+        // Every repo in a project checks out the same branch, so a repo placeholder is required
+        assert!(validate_worktree_location("~/wt/{branch}").is_err());
+        assert!(validate_worktree_location("~/wt/{repo}").is_err());
+    }
+
+    #[test]
+    fn test_add_worktree_uses_info_exclude() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_path = fs::canonicalize(temp_dir.path()).unwrap();
+        init_repo(&repo_path);
+        let repo = Repo { name: "r".to_string(), path: repo_path.clone() };
+
+        let (out, wt_path) = repo.add_worktree("feat/one", DEFAULT_WORKTREE_LOCATION, None).unwrap();
+        assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+        assert!(wt_path.join(".git").exists());
+        assert!(!repo_path.join(".gitignore").exists());
+
+        let exclude = fs::read_to_string(repo_path.join(".git/info/exclude")).unwrap();
+        assert_eq!(exclude.lines().filter(|l| *l == "/.workman/").count(), 1);
+
+        // A second worktree does not duplicate the exclude entry
+        let (out, _) = repo.add_worktree("feat/two", DEFAULT_WORKTREE_LOCATION, None).unwrap();
+        assert!(out.status.success());
+        let exclude = fs::read_to_string(repo_path.join(".git/info/exclude")).unwrap();
+        assert_eq!(exclude.lines().filter(|l| *l == "/.workman/").count(), 1);
+    }
+
+    #[test]
+    fn test_add_worktree_reports_occupied_directory() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = fs::canonicalize(temp_dir.path()).unwrap();
+        let repo_path = root.join("repo");
+        fs::create_dir(&repo_path).unwrap();
+        init_repo(&repo_path);
+        let repo = Repo { name: "repo".to_string(), path: repo_path.clone() };
+
+        // A template without {branch} forces every branch into the same folder
+        let fixed = format!("{}/fixed", root.display());
+        let (out, _) = repo.add_worktree("one", &fixed, None).unwrap();
+        assert!(out.status.success());
+        let err = repo.add_worktree("two", &fixed, None).unwrap_err().to_string();
+        assert!(err.contains("already exists for branch 'one'"), "{}", err);
+        let err = repo.add_worktree("one", &fixed, None).unwrap_err().to_string();
+        assert!(err.contains("A worktree for 'one' already exists"), "{}", err);
+
+        // Distinct branches that used to collide now get distinct folders
+        let (out, a) = repo.add_worktree("feat/a-b", DEFAULT_WORKTREE_LOCATION, None).unwrap();
+        assert!(out.status.success());
+        let (out, b) = repo.add_worktree("feat-a/b", DEFAULT_WORKTREE_LOCATION, None).unwrap();
+        assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+        assert_ne!(a, b);
+
+        // A non-empty directory that is not a worktree is refused
+        let stray = repo.worktree_path("stray", DEFAULT_WORKTREE_LOCATION);
+        fs::create_dir_all(&stray).unwrap();
+        fs::write(stray.join("notes.txt"), "x").unwrap();
+        let err = repo.add_worktree("stray", DEFAULT_WORKTREE_LOCATION, None).unwrap_err().to_string();
+        assert!(err.contains("is not a worktree for 'stray'"), "{}", err);
+    }
+
+    #[test]
+    fn test_add_worktree_outside_repo() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = fs::canonicalize(temp_dir.path()).unwrap();
+        let repo_path = root.join("repo");
+        fs::create_dir(&repo_path).unwrap();
+        init_repo(&repo_path);
+        let repo = Repo { name: "repo".to_string(), path: repo_path.clone() };
+
+        let location = format!("{}/wt/{{repo}}/{{branch}}", root.display());
+        let (out, wt_path) = repo.add_worktree("main-2", &location, None).unwrap();
+        assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+        assert_eq!(wt_path, root.join("wt/repo/main-2"));
+        let exclude = fs::read_to_string(repo_path.join(".git/info/exclude")).unwrap_or_default();
+        assert!(!exclude.contains("workman"));
     }
 
     #[test]
@@ -384,6 +753,30 @@ mod tests {
     #[test]
     fn test_settings_default() {
         let s = Settings::default();
-        assert!(!s.use_tmux);
+        assert_eq!(s.multiplexer(), Multiplexer::Builtin);
+        assert!(s.hooks.pre_push.is_empty());
+        assert_eq!(s.worktree_location(), DEFAULT_WORKTREE_LOCATION);
+    }
+
+    #[test]
+    fn test_settings_multiplexer() {
+        let mut s: Settings = serde_json::from_str(r#"{"use_tmux": true}"#).unwrap();
+        assert_eq!(s.multiplexer(), Multiplexer::Tmux);
+        s.set_multiplexer(Multiplexer::Builtin);
+        assert_eq!(s.multiplexer(), Multiplexer::Builtin);
+        s.set_multiplexer(Multiplexer::Zellij);
+        let json = serde_json::to_string(&s).unwrap();
+        assert!(json.contains(r#""multiplexer":"zellij""#) && !json.contains("use_tmux"));
+
+        // An unknown backend falls back to the built-in terminal
+        let s: Settings = serde_json::from_str(r#"{"multiplexer": "byobu"}"#).unwrap();
+        assert_eq!(s.multiplexer(), Multiplexer::Builtin);
+    }
+
+    #[test]
+    fn test_settings_hooks_deserialize() {
+        let s: Settings = serde_json::from_str(r#"{"hooks": {"pre_push": ["cargo clippy"]}}"#).unwrap();
+        assert_eq!(s.hooks.pre_push, vec!["cargo clippy".to_string()]);
+        assert!(s.hooks.pre_remove.is_empty());
     }
 }
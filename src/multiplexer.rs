@@ -0,0 +1,239 @@
+//! Terminal multiplexer backends. The built-in PTY is the default; tmux, zellij and GNU
+//! screen run one named session per project and worktree, `workman-<project>` or
+//! `workman-<project>-<repo>`.
+
+use anyhow::Result;
+use std::collections::HashSet;
+use std::path::Path;
+use std::process::Command;
+
+const PREFIX: &str = "workman-";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Multiplexer {
+    /// Embedded terminals (`portable-pty` + `vt100`).
+    #[default]
+    Builtin,
+    Tmux,
+    Zellij,
+    Screen,
+}
+
+impl Multiplexer {
+    /// In the order the Options row cycles through them.
+    pub const ALL: &'static [Multiplexer] =
+        &[Multiplexer::Builtin, Multiplexer::Tmux, Multiplexer::Zellij, Multiplexer::Screen];
+
+    /// Name used in the config file.
+    pub fn name(self) -> &'static str {
+        match self {
+            Multiplexer::Builtin => "builtin",
+            Multiplexer::Tmux => "tmux",
+            Multiplexer::Zellij => "zellij",
+            Multiplexer::Screen => "screen",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|m| m.name().eq_ignore_ascii_case(s.trim()))
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Multiplexer::Builtin => "Built-in",
+            Multiplexer::Tmux => "tmux",
+            Multiplexer::Zellij => "zellij",
+            Multiplexer::Screen => "GNU screen",
+        }
+    }
+
+    /// The backend after this one in `ALL`, wrapping around.
+    pub fn next(self) -> Self {
+        let idx = Self::ALL.iter().position(|m| *m == self).unwrap_or(0);
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
+
+    /// Whether sessions run in an external program instead of the built-in terminal.
+    pub fn is_external(self) -> bool {
+        self != Multiplexer::Builtin
+    }
+
+    /// Whether workman itself runs inside this multiplexer, so sessions are opened in
+    /// place (see `open`) instead of suspending workman to attach.
+    pub fn inside(self) -> bool {
+        let var = match self {
+            Multiplexer::Builtin => return false,
+            Multiplexer::Tmux => "TMUX",
+            Multiplexer::Zellij => "ZELLIJ",
+            Multiplexer::Screen => "STY",
+        };
+        std::env::var_os(var).is_some_and(|v| !v.is_empty())
+    }
+
+    /// The workman sessions this multiplexer has; none if it isn't running or installed.
+    pub fn list_sessions(self) -> HashSet<String> {
+        let args: &[&str] = match self {
+            Multiplexer::Builtin => return HashSet::new(),
+            Multiplexer::Tmux => &["list-sessions", "-F", "#{session_name}"],
+            Multiplexer::Zellij => &["list-sessions", "--no-formatting"],
+            Multiplexer::Screen => &["-ls"],
+        };
+        // `screen -ls` exits non-zero even when it lists sessions, so only the output counts
+        Command::new(self.name())
+            .args(args)
+            .output()
+            .map(|out| self.parse_sessions(&String::from_utf8_lossy(&out.stdout)))
+            .unwrap_or_default()
+    }
+
+    fn parse_sessions(self, out: &str) -> HashSet<String> {
+        out.lines()
+            .filter_map(|line| {
+                let first = line.split_whitespace().next()?;
+                match self {
+                    // `<pid>.<name>  (Detached)`
+                    Multiplexer::Screen => first.split_once('.').map(|(_, name)| name),
+                    // zellij: `<name> [Created 1h ago]`
+                    _ => Some(first),
+                }
+            })
+            .filter(|name| name.starts_with(PREFIX))
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Command that attaches the terminal to session `name`, creating it in `path` if
+    /// needed. workman runs it while suspended.
+    pub fn attach_command(self, name: &str, path: &Path) -> Command {
+        let mut cmd = Command::new(self.name());
+        match self {
+            Multiplexer::Tmux => {
+                cmd.args(["new-session", "-A", "-s", name, "-c"]).arg(path);
+            }
+            Multiplexer::Zellij => {
+                cmd.args(["attach", "--create", name]);
+            }
+            Multiplexer::Screen if self.list_sessions().contains(name) => {
+                cmd.args(["-d", "-r", name]);
+            }
+            Multiplexer::Screen => {
+                cmd.args(["-S", name]);
+            }
+            Multiplexer::Builtin => {}
+        }
+        // zellij and screen start new sessions in the current directory
+        cmd.current_dir(path);
+        cmd
+    }
+
+    /// From inside the multiplexer: tmux creates session `name` if needed and switches
+    /// this client to it, so `prefix L` returns to workman. zellij and screen can't
+    /// switch sessions from within one, so they open a tab or window named `name` in
+    /// the current session instead.
+    pub fn open(self, name: &str, path: &Path) -> Result<()> {
+        match self {
+            Multiplexer::Builtin => Ok(()),
+            Multiplexer::Tmux => {
+                let target = format!("={}", name);
+                let exists = Command::new("tmux").args(["has-session", "-t", &target]).output()?.status.success();
+                if !exists {
+                    run(Command::new("tmux").args(["new-session", "-d", "-s", name, "-c"]).arg(path))?;
+                }
+                run(Command::new("tmux").args(["switch-client", "-t", &target]))
+            }
+            Multiplexer::Zellij => {
+                run(Command::new("zellij").args(["action", "new-tab", "--name", name, "--cwd"]).arg(path))
+            }
+            Multiplexer::Screen => {
+                run(Command::new("screen").args(["-X", "chdir"]).arg(path))?;
+                run(Command::new("screen").args(["-X", "screen", "-t", name]))
+            }
+        }
+    }
+
+    /// Ends session `name` and everything running in it. A missing session is not an error.
+    pub fn kill_session(self, name: &str) {
+        let target = format!("={}", name);
+        let commands: &[&[&str]] = match self {
+            Multiplexer::Builtin => &[],
+            Multiplexer::Tmux => &[&["kill-session", "-t", &target]],
+            // Killed sessions stay resurrectable until deleted
+            Multiplexer::Zellij => &[&["kill-session", name], &["delete-session", name]],
+            Multiplexer::Screen => &[&["-S", name, "-X", "quit"]],
+        };
+        for args in commands {
+            let _ = Command::new(self.name()).args(*args).output();
+        }
+    }
+}
+
+/// Name of the session for a project (`repo` is `None`) or one of its worktrees, as
+/// `workman-<project>__<repo>`.
+///
+/// Letters, digits and `-` are kept; any other character is escaped as `_` and the hex of
+/// each byte. Encoded parts never contain `__`, so distinct rows never share a session
+/// (removing one kills its session).
+pub fn session_name(project: &str, repo: Option<&str>) -> String {
+    let encode = |part: &str| {
+        let mut out = String::with_capacity(part.len());
+        for c in part.chars() {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                out.push(c);
+            } else {
+                let mut buf = [0u8; 4];
+                for byte in c.encode_utf8(&mut buf).bytes() {
+                    out.push_str(&format!("_{:02X}", byte));
+                }
+            }
+        }
+        out
+    };
+    match repo {
+        Some(repo) => format!("{}{}__{}", PREFIX, encode(project), encode(repo)),
+        None => format!("{}{}", PREFIX, encode(project)),
+    }
+}
+
+fn run(cmd: &mut Command) -> Result<()> {
+    let out = cmd.output()?;
+    if !out.status.success() {
+        return Err(anyhow::anyhow!("{}", String::from_utf8_lossy(&out.stderr).trim()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_names() {
+        assert_eq!(session_name("My App", Some("api.v2")), "workman-My_20App__api_2Ev2");
+        assert_eq!(session_name("my-app", None), "workman-my-app");
+        // Neither the separator nor punctuation lets two rows share a name
+        assert_ne!(session_name("a", Some("b-c")), session_name("a-b", Some("c")));
+        assert_ne!(session_name("a", Some("_b")), session_name("a_", Some("b")));
+        assert_ne!(session_name("a.b", None), session_name("a-b", None));
+        assert_ne!(session_name("a__b", None), session_name("a", Some("b")));
+    }
+
+    #[test]
+    fn test_parse_sessions() {
+        let tmux = Multiplexer::Tmux.parse_sessions("main\nworkman-proj\nworkman-proj-api\n");
+        assert_eq!(tmux.len(), 2);
+        assert!(tmux.contains("workman-proj-api"));
+
+        let zellij = Multiplexer::Zellij.parse_sessions(
+            "workman-proj [Created 2h ago]\nother [Created 1m ago] (current)\n",
+        );
+        assert_eq!(zellij, HashSet::from(["workman-proj".to_string()]));
+
+        let screen = Multiplexer::Screen.parse_sessions(
+            "There are screens on:\n\t4242.workman-proj-api\t(Detached)\n\t77.pts-0.host\t(Attached)\n2 Sockets in /run/screen.\n",
+        );
+        assert_eq!(screen, HashSet::from(["workman-proj-api".to_string()]));
+
+        assert_eq!(Multiplexer::parse(" Zellij "), Some(Multiplexer::Zellij));
+        assert_eq!(Multiplexer::Screen.next(), Multiplexer::Builtin);
+    }
+}
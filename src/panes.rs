@@ -0,0 +1,254 @@
+use crate::app::Selection;
+use ratatui::layout::Rect;
+
+/// How a split divides its area.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SplitDirection {
+    /// Side by side.
+    Horizontal,
+    /// Stacked.
+    Vertical,
+}
+
+/// A direction to move focus in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FocusMove {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+#[derive(Debug)]
+enum Node {
+    Pane(Selection),
+    Split { direction: SplitDirection, first: Box<Node>, second: Box<Node> },
+}
+
+/// The terminal panes of the output panel: a tree of splits with one pane focused.
+/// Panes are numbered in reading order (left to right, top to bottom). The focused
+/// pane always shows the tree selection; the others keep what they showed.
+#[derive(Debug)]
+pub struct Panes {
+    root: Node,
+    pub focused: usize,
+}
+
+impl Panes {
+    pub fn new(sel: Selection) -> Self {
+        Self { root: Node::Pane(sel), focused: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        fn count(node: &Node) -> usize {
+            match node {
+                Node::Pane(_) => 1,
+                Node::Split { first, second, .. } => count(first) + count(second),
+            }
+        }
+        count(&self.root)
+    }
+
+    /// Each pane's selection and area within `area`, in pane order.
+    pub fn layout(&self, area: Rect) -> Vec<(Selection, Rect)> {
+        fn walk(node: &Node, area: Rect, out: &mut Vec<(Selection, Rect)>) {
+            match node {
+                Node::Pane(sel) => out.push((*sel, area)),
+                Node::Split { direction, first, second } => {
+                    let (a, b) = match direction {
+                        SplitDirection::Horizontal => {
+                            let w = area.width / 2;
+                            (Rect { width: w, ..area }, Rect { x: area.x + w, width: area.width - w, ..area })
+                        }
+                        SplitDirection::Vertical => {
+                            let h = area.height / 2;
+                            (Rect { height: h, ..area }, Rect { y: area.y + h, height: area.height - h, ..area })
+                        }
+                    };
+                    walk(first, a, out);
+                    walk(second, b, out);
+                }
+            }
+        }
+        let mut out = Vec::new();
+        walk(&self.root, area, &mut out);
+        out
+    }
+
+    /// Points the focused pane at `sel`.
+    pub fn show(&mut self, sel: Selection) {
+        if let Some(node) = self.leaf_mut(self.focused) {
+            *node = Node::Pane(sel);
+        }
+    }
+
+    /// Splits the focused pane in two; the new half shows the same selection and takes focus.
+    pub fn split(&mut self, direction: SplitDirection) {
+        if let Some(node) = self.leaf_mut(self.focused)
+            && let Node::Pane(sel) = *node
+        {
+            *node = Node::Split {
+                direction,
+                first: Box::new(Node::Pane(sel)),
+                second: Box::new(Node::Pane(sel)),
+            };
+            self.focused += 1;
+        }
+    }
+
+    /// Closes pane `idx`, giving its space to its sibling. The last pane can't be closed.
+    pub fn close(&mut self, idx: usize) -> bool {
+        fn without(node: Node, idx: &mut Option<usize>) -> Option<Node> {
+            match node {
+                Node::Pane(sel) => match *idx {
+                    Some(0) => {
+                        *idx = None;
+                        None
+                    }
+                    Some(n) => {
+                        *idx = Some(n - 1);
+                        Some(Node::Pane(sel))
+                    }
+                    None => Some(Node::Pane(sel)),
+                },
+                Node::Split { direction, first, second } => {
+                    match (without(*first, idx), without(*second, idx)) {
+                        (Some(first), Some(second)) => {
+                            Some(Node::Split { direction, first: Box::new(first), second: Box::new(second) })
+                        }
+                        (Some(only), None) | (None, Some(only)) => Some(only),
+                        (None, None) => None,
+                    }
+                }
+            }
+        }
+        if self.len() <= 1 || idx >= self.len() {
+            return false;
+        }
+        let root = std::mem::replace(&mut self.root, Node::Pane(Selection::Project(0)));
+        self.root = without(root, &mut Some(idx)).expect("at least one pane remains");
+        if self.focused > idx || self.focused >= self.len() {
+            self.focused = self.focused.saturating_sub(1);
+        }
+        true
+    }
+
+    /// Applies `f` to every pane's selection, closing panes it maps to `None`
+    /// (as long as one pane remains). Used when projects and worktrees are removed.
+    pub fn remap(&mut self, f: impl Fn(Selection) -> Option<Selection>) {
+        let mut idx = 0;
+        while idx < self.len() {
+            let sel = self.selection(idx).expect("idx < len");
+            match f(sel) {
+                Some(new) => {
+                    if let Some(node) = self.leaf_mut(idx) {
+                        *node = Node::Pane(new);
+                    }
+                    idx += 1;
+                }
+                None if self.close(idx) => {}
+                None => idx += 1,
+            }
+        }
+    }
+
+    pub fn selection(&self, idx: usize) -> Option<Selection> {
+        self.layout(Rect::default()).get(idx).map(|(sel, _)| *sel)
+    }
+
+    /// The pane next to the focused one in direction `dir`, measured on `area`.
+    pub fn neighbour(&self, area: Rect, dir: FocusMove) -> Option<usize> {
+        let rects: Vec<Rect> = self.layout(area).into_iter().map(|(_, r)| r).collect();
+        let cur = *rects.get(self.focused)?;
+        let (cx, cy) = (cur.x as i32 * 2 + cur.width as i32, cur.y as i32 * 2 + cur.height as i32);
+        rects.iter().enumerate()
+            .filter(|(i, r)| {
+                *i != self.focused && match dir {
+                    FocusMove::Left => r.right() <= cur.left(),
+                    FocusMove::Right => r.left() >= cur.right(),
+                    FocusMove::Up => r.bottom() <= cur.top(),
+                    FocusMove::Down => r.top() >= cur.bottom(),
+                }
+            })
+            // Closest in that direction first, then the one most in line with the focused pane
+            .min_by_key(|(_, r)| {
+                let (rx, ry) = (r.x as i32 * 2 + r.width as i32, r.y as i32 * 2 + r.height as i32);
+                match dir {
+                    FocusMove::Left | FocusMove::Right => ((rx - cx).abs(), (ry - cy).abs()),
+                    FocusMove::Up | FocusMove::Down => ((ry - cy).abs(), (rx - cx).abs()),
+                }
+            })
+            .map(|(i, _)| i)
+    }
+
+    fn leaf_mut(&mut self, idx: usize) -> Option<&mut Node> {
+        fn walk<'a>(node: &'a mut Node, idx: &mut usize) -> Option<&'a mut Node> {
+            match node {
+                Node::Pane(_) if *idx == 0 => Some(node),
+                Node::Pane(_) => {
+                    *idx -= 1;
+                    None
+                }
+                Node::Split { first, second, .. } => walk(first, idx).or_else(|| walk(second, idx)),
+            }
+        }
+        walk(&mut self.root, &mut { idx })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AREA: Rect = Rect { x: 0, y: 0, width: 80, height: 40 };
+
+    /// Two panes side by side, the right one split into two stacked panes.
+    fn three_panes() -> Panes {
+        let mut panes = Panes::new(Selection::Project(0));
+        panes.split(SplitDirection::Horizontal);
+        panes.show(Selection::Worktree(0, 0));
+        panes.split(SplitDirection::Vertical);
+        panes.show(Selection::Worktree(0, 1));
+        panes
+    }
+
+    #[test]
+    fn test_split_layout() {
+        let panes = three_panes();
+        assert_eq!(panes.focused, 2);
+        let layout = panes.layout(AREA);
+        assert_eq!(layout, vec![
+            (Selection::Project(0), Rect::new(0, 0, 40, 40)),
+            (Selection::Worktree(0, 0), Rect::new(40, 0, 40, 20)),
+            (Selection::Worktree(0, 1), Rect::new(40, 20, 40, 20)),
+        ]);
+    }
+
+    #[test]
+    fn test_neighbour() {
+        let mut panes = three_panes();
+        assert_eq!(panes.neighbour(AREA, FocusMove::Up), Some(1));
+        assert_eq!(panes.neighbour(AREA, FocusMove::Left), Some(0));
+        assert_eq!(panes.neighbour(AREA, FocusMove::Right), None);
+        panes.focused = 0;
+        // Both right panes touch; the top one is as close, so it wins the tie on order
+        assert_eq!(panes.neighbour(AREA, FocusMove::Right), Some(1));
+    }
+
+    #[test]
+    fn test_close_and_remap() {
+        let mut panes = three_panes();
+        assert!(panes.close(1));
+        assert_eq!(panes.focused, 1);
+        assert_eq!(panes.layout(AREA)[1], (Selection::Worktree(0, 1), Rect::new(40, 0, 40, 40)));
+
+        // Removing worktree 1 closes its pane; the last pane always stays
+        panes.remap(|sel| match sel {
+            Selection::Worktree(0, 1) => None,
+            other => Some(other),
+        });
+        assert_eq!(panes.len(), 1);
+        assert_eq!(panes.focused, 0);
+        assert!(!panes.close(0));
+    }
+}
@@ -0,0 +1,222 @@
+use crate::commands::UserCommand;
+use crate::hooks::Hooks;
+use anyhow::Result;
+use serde::Deserialize;
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+/// Name of the per-repo config file, checked in at the root of the main checkout.
+pub const REPO_CONFIG_FILE: &str = ".workman.toml";
+
+/// Per-repo settings read from `<repo>/.workman.toml`.
+///
+/// ```toml
+/// [setup]
+/// copy = [".env", "config/local.yml"]
+/// symlink = ["node_modules"]
+/// commands = ["npm install"]
+///
+/// [hooks]
+/// pre_push = ["cargo clippy -- -D warnings"]
+///
+/// [[commands]]
+/// name = "test"
+/// command = "cargo test"
+/// ```
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct RepoConfig {
+    #[serde(default)]
+    pub setup: SetupConfig,
+    #[serde(default)]
+    pub hooks: Hooks,
+    /// Commands offered by the command palette on this repo's worktrees.
+    #[serde(default)]
+    pub commands: Vec<UserCommand>,
+}
+
+/// Steps run against a freshly created worktree.
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct SetupConfig {
+    /// Paths (relative to the repo root) copied from the main checkout.
+    #[serde(default)]
+    pub copy: Vec<PathBuf>,
+    /// Paths (relative to the repo root) symlinked to the main checkout.
+    #[serde(default)]
+    pub symlink: Vec<PathBuf>,
+    /// Shell commands run inside the new worktree, in order.
+    #[serde(default)]
+    pub commands: Vec<String>,
+}
+
+impl RepoConfig {
+    /// Loads `.workman.toml` from the repo root. A missing file is not an error.
+    pub fn load(repo_path: &Path) -> Result<Self> {
+        let path = repo_path.join(REPO_CONFIG_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path)?;
+        toml::from_str(&content).map_err(|e| anyhow::anyhow!("Invalid {}: {}", REPO_CONFIG_FILE, e))
+    }
+}
+
+impl SetupConfig {
+    /// Copies and symlinks the configured paths from `source` (the main checkout) into `dest`
+    /// (the new worktree). Returns one log line per entry; failures are reported, not fatal.
+    pub fn link_files(&self, source: &Path, dest: &Path) -> Vec<String> {
+        let mut log = Vec::new();
+
+        for rel in &self.copy {
+            let result = checked_relative(rel).and_then(|rel| {
+                let from = source.join(rel);
+                let to = dest.join(rel);
+                if !from.exists() {
+                    return Err(anyhow::anyhow!("not found in main checkout"));
+                }
+                if let Some(parent) = to.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                copy_recursive(&from, &to)
+            });
+            log.push(match result {
+                Ok(()) => format!("copied {}", rel.display()),
+                Err(e) => format!("copy {} failed: {}", rel.display(), e),
+            });
+        }
+
+        for rel in &self.symlink {
+            let result = checked_relative(rel).and_then(|rel| {
+                let from = source.join(rel);
+                let to = dest.join(rel);
+                if !from.exists() {
+                    return Err(anyhow::anyhow!("not found in main checkout"));
+                }
+                if to.symlink_metadata().is_ok() {
+                    return Err(anyhow::anyhow!("already exists in worktree"));
+                }
+                if let Some(parent) = to.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                std::os::unix::fs::symlink(&from, &to)?;
+                Ok(())
+            });
+            log.push(match result {
+                Ok(()) => format!("linked {}", rel.display()),
+                Err(e) => format!("link {} failed: {}", rel.display(), e),
+            });
+        }
+
+        log
+    }
+}
+
+/// Rejects absolute paths and `..` so a checked-in config can't reach outside the repo.
+fn checked_relative(path: &Path) -> Result<&Path> {
+    if path.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir)) {
+        Ok(path)
+    } else {
+        Err(anyhow::anyhow!("path must be relative to the repo root"))
+    }
+}
+
+fn copy_recursive(from: &Path, to: &Path) -> Result<()> {
+    if from.is_dir() {
+        fs::create_dir_all(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+        }
+    } else {
+        fs::copy(from, to)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_missing_file_is_default() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = RepoConfig::load(temp_dir.path()).unwrap();
+        assert!(config.setup.copy.is_empty());
+        assert!(config.setup.commands.is_empty());
+        assert!(config.hooks.pre_push.is_empty());
+    }
+
+    #[test]
+    fn test_load_setup_section() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::write(
+            temp_dir.path().join(REPO_CONFIG_FILE),
+            "[setup]\ncopy = [\".env\"]\nsymlink = [\"node_modules\"]\ncommands = [\"npm install\"]\n",
+        ).unwrap();
+
+        let config = RepoConfig::load(temp_dir.path()).unwrap();
+        assert_eq!(config.setup.copy, vec![PathBuf::from(".env")]);
+        assert_eq!(config.setup.symlink, vec![PathBuf::from("node_modules")]);
+        assert_eq!(config.setup.commands, vec!["npm install".to_string()]);
+    }
+
+    #[test]
+    fn test_load_hooks_section() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::write(
+            temp_dir.path().join(REPO_CONFIG_FILE),
+            "[hooks]\npre_remove = [\"docker compose down\"]\n",
+        ).unwrap();
+
+        let config = RepoConfig::load(temp_dir.path()).unwrap();
+        assert_eq!(config.hooks.pre_remove, vec!["docker compose down".to_string()]);
+        assert!(config.setup.commands.is_empty());
+    }
+
+    #[test]
+    fn test_load_commands() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::write(
+            temp_dir.path().join(REPO_CONFIG_FILE),
+            "[[commands]]\nname = \"test\"\ncommand = \"cargo test\"\n\n\
+             [[commands]]\nname = \"server\"\ncommand = \"cargo run\"\nterminal = true\n",
+        ).unwrap();
+
+        let config = RepoConfig::load(temp_dir.path()).unwrap();
+        assert_eq!(config.commands.len(), 2);
+        assert_eq!(config.commands[0].command, "cargo test");
+        assert!(!config.commands[0].terminal);
+        assert!(config.commands[1].terminal);
+    }
+
+    #[test]
+    fn test_load_invalid_toml_errors() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::write(temp_dir.path().join(REPO_CONFIG_FILE), "[setup\n").unwrap();
+        assert!(RepoConfig::load(temp_dir.path()).is_err());
+    }
+
+    #[test]
+    fn test_link_files() {
+        let source = tempfile::tempdir().unwrap();
+        let dest = tempfile::tempdir().unwrap();
+        fs::write(source.path().join(".env"), "SECRET=1").unwrap();
+        fs::create_dir_all(source.path().join("config")).unwrap();
+        fs::write(source.path().join("config/local.yml"), "a: b").unwrap();
+        fs::create_dir(source.path().join("node_modules")).unwrap();
+
+        let setup = SetupConfig {
+            copy: vec![PathBuf::from(".env"), PathBuf::from("config"), PathBuf::from("missing")],
+            symlink: vec![PathBuf::from("node_modules"), PathBuf::from("../escape")],
+            commands: vec![],
+        };
+        let log = setup.link_files(source.path(), dest.path());
+
+        assert_eq!(fs::read_to_string(dest.path().join(".env")).unwrap(), "SECRET=1");
+        assert_eq!(fs::read_to_string(dest.path().join("config/local.yml")).unwrap(), "a: b");
+        assert!(dest.path().join("node_modules").symlink_metadata().unwrap().file_type().is_symlink());
+        assert!(log.iter().any(|l| l.starts_with("copy missing failed")));
+        assert!(log.iter().any(|l| l.starts_with("link ../escape failed")));
+    }
+}
@@ -2,16 +2,56 @@ use anyhow::Result;
 use portable_pty::{native_pty_system, CommandBuilder, PtySize};
 use std::sync::{Arc, Mutex};
 use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
 use vt100::Parser; // Removed Screen import here
 
+use crate::daemon::{self, Frame, Reply, Request, SessionInfo};
+use crate::tasks::OutputNotifier;
+
+/// Where a session's process lives.
+enum Backend {
+    /// A PTY owned by workman; the process ends with the session.
+    Local {
+        writer: Box<dyn Write + Send>,
+        master: Box<dyn portable_pty::MasterPty + Send>,
+        /// Taken when the session is dropped, to be reaped in the background.
+        child: Option<Box<dyn portable_pty::Child + Send + Sync>>,
+    },
+    /// A PTY owned by the session daemon, attached over `stream`.
+    Daemon {
+        stream: UnixStream,
+        pid: Option<u32>,
+        /// Set by the reader when the daemon reports the exit.
+        exited: Arc<Mutex<Option<u32>>>,
+        /// Leave the process running when the session is dropped; see `detach`.
+        keep_alive: bool,
+    },
+}
+
 pub struct Session {
     pub parser: Arc<Mutex<Parser>>,
-    pub writer: Box<dyn Write + Send>,
-    pub master: Box<dyn portable_pty::MasterPty + Send>,
+    backend: Backend,
+    /// Set once the shell has exited; see `poll_exit`.
+    pub exit_code: Option<u32>,
+    /// User-chosen name shown on the tab and in the session switcher.
+    pub name: Option<String>,
+    /// Command the tab was started with (run by `$SHELL -c`); `None` for an interactive shell.
+    pub command: Option<String>,
+    /// Directory the shell was started in.
+    pub start_dir: PathBuf,
+    last_output: Arc<Mutex<Instant>>,
 }
 
 impl Session {
-    pub fn new(path: std::path::PathBuf, width: u16, height: u16) -> Result<Self> {
+    pub fn new(
+        path: PathBuf,
+        command: Option<String>,
+        width: u16,
+        height: u16,
+        notifier: OutputNotifier,
+    ) -> Result<Self> {
         let pty_system = native_pty_system();
         let pair = pty_system.openpty(PtySize {
             rows: height,
@@ -22,13 +62,18 @@ impl Session {
 
         let shell = std::env::var("SHELL").unwrap_or_else(|_| "sh".to_string());
         let mut cmd = CommandBuilder::new(shell);
-        cmd.cwd(path);
+        if let Some(command) = &command {
+            cmd.args(["-c", command]);
+        }
+        cmd.cwd(&path);
+
+        let child = pair.slave.spawn_command(cmd)?;
 
-        let mut _child = pair.slave.spawn_command(cmd)?;
-        
         let parser = Arc::new(Mutex::new(Parser::new(height, width, 1000)));
         let parser_clone = parser.clone();
-        
+        let last_output = Arc::new(Mutex::new(Instant::now()));
+        let last_output_clone = last_output.clone();
+
         let mut reader = pair.master.try_clone_reader()?;
         let writer = pair.master.take_writer()?;
         let master = pair.master;
@@ -41,34 +86,426 @@ impl Session {
                     Ok(n) => {
                         let mut p = parser_clone.lock().unwrap();
                         p.process(&buf[..n]);
+                        *last_output_clone.lock().unwrap() = Instant::now();
+                        notifier.notify();
                     }
                     Err(_) => break,
                 }
             }
+            notifier.notify();
         });
 
         Ok(Self {
             parser,
-            writer,
-            master,
+            backend: Backend::Local { writer, master, child: Some(child) },
+            exit_code: None,
+            name: None,
+            command,
+            start_dir: path,
+            last_output,
         })
     }
 
+    /// Starts a session in the daemon listening on `socket`, starting the daemon if needed.
+    pub fn new_persistent(
+        socket: &Path,
+        path: PathBuf,
+        command: Option<String>,
+        width: u16,
+        height: u16,
+        notifier: OutputNotifier,
+    ) -> Result<Self> {
+        let request = Request::Spawn { dir: path, command, cols: width, rows: height };
+        let (stream, info) = daemon::open(socket, &request)?;
+        Self::attached(stream, info, notifier)
+    }
+
+    /// Reattaches to a daemon session left running by an earlier workman, at its
+    /// current size. The screen is replayed as it was.
+    pub fn reattach(socket: &Path, id: u64, notifier: OutputNotifier) -> Result<Self> {
+        let (stream, info) = daemon::open(socket, &Request::Attach { id, cols: 0, rows: 0 })?;
+        Self::attached(stream, info, notifier)
+    }
+
+    fn attached(stream: UnixStream, info: SessionInfo, notifier: OutputNotifier) -> Result<Self> {
+        let parser = Arc::new(Mutex::new(Parser::new(info.rows.max(1), info.cols.max(1), 1000)));
+        let parser_clone = parser.clone();
+        let last_output = Arc::new(Mutex::new(Instant::now()));
+        let last_output_clone = last_output.clone();
+        let exited = Arc::new(Mutex::new(None));
+        let exited_clone = exited.clone();
+
+        let mut reader = stream.try_clone()?;
+        tokio::task::spawn_blocking(move || {
+            loop {
+                match daemon::read_reply(&mut reader) {
+                    Ok(Frame::Output(data)) => {
+                        parser_clone.lock().unwrap().process(&data);
+                        *last_output_clone.lock().unwrap() = Instant::now();
+                        notifier.notify();
+                    }
+                    Ok(Frame::Reply(Reply::Exited { code })) => {
+                        *exited_clone.lock().unwrap() = Some(code);
+                        notifier.notify();
+                    }
+                    Ok(Frame::Reply(_)) => {}
+                    Err(_) => break,
+                }
+            }
+            // The connection dropped without an exit (the daemon died): treat it as one
+            let mut exited = exited_clone.lock().unwrap();
+            if exited.is_none() {
+                parser_clone.lock().unwrap()
+                    .process(b"\r\n\x1b[0m[lost connection to the session daemon]\r\n");
+                *exited = Some(1);
+            }
+            notifier.notify();
+        });
+
+        Ok(Self {
+            parser,
+            backend: Backend::Daemon { stream, pid: info.pid, exited, keep_alive: false },
+            exit_code: None,
+            name: info.name,
+            command: info.command,
+            start_dir: info.dir,
+            last_output,
+        })
+    }
+
+    /// Tab label: the custom name, else the command, else `shell`.
+    pub fn label(&self) -> &str {
+        self.name.as_deref()
+            .or(self.command.as_deref())
+            .unwrap_or("shell")
+    }
+
+    /// Sets or clears the tab's name, remembering it in the daemon for persistent sessions.
+    pub fn set_name(&mut self, name: Option<String>) {
+        if let Backend::Daemon { stream, .. } = &mut self.backend {
+            let _ = daemon::send_request(stream, &Request::Rename { name: name.clone() });
+        }
+        self.name = name;
+    }
+
+    /// Lets a persistent session's process keep running in the daemon after this
+    /// session is dropped, for the next workman to reattach. Exited ones are cleared
+    /// away instead. Local sessions always end with workman.
+    pub fn detach(&mut self) {
+        let live = self.exit_code.is_none();
+        if let Backend::Daemon { keep_alive, .. } = &mut self.backend {
+            *keep_alive = live;
+        }
+    }
+
+    /// When the shell last printed anything.
+    pub fn last_activity(&self) -> Instant {
+        *self.last_output.lock().unwrap()
+    }
+
+    /// Process group in the terminal's foreground.
+    fn foreground_pid(&self) -> Option<i32> {
+        match &self.backend {
+            Backend::Local { master, .. } => master.process_group_leader(),
+            Backend::Daemon { pid, .. } => {
+                let pid = (*pid)?;
+                // Field 8 of stat is tpgid; the command (field 2) may contain spaces
+                if let Ok(stat) = std::fs::read_to_string(format!("/proc/{}/stat", pid)) {
+                    return stat.rsplit(')').next()?.split_whitespace().nth(5)?.parse().ok();
+                }
+                let out = std::process::Command::new("ps")
+                    .args(["-o", "tpgid=", "-p", &pid.to_string()])
+                    .output().ok()?;
+                String::from_utf8_lossy(&out.stdout).trim().parse().ok()
+            }
+        }
+        .filter(|pid| *pid > 0)
+    }
+
+    /// Name of the program in the terminal's foreground, e.g. `vim`, or the shell when idle.
+    pub fn foreground_command(&self) -> Option<String> {
+        let pid = self.foreground_pid()?;
+        if let Ok(comm) = std::fs::read_to_string(format!("/proc/{}/comm", pid)) {
+            return Some(comm.trim().to_string());
+        }
+        let out = std::process::Command::new("ps")
+            .args(["-o", "comm=", "-p", &pid.to_string()])
+            .output().ok()?;
+        let comm = String::from_utf8_lossy(&out.stdout).trim().to_string();
+        // macOS reports the full path
+        let name = comm.rsplit('/').next().unwrap_or_default().to_string();
+        (!name.is_empty()).then_some(name)
+    }
+
+    /// Working directory of the foreground program where the OS exposes it (Linux),
+    /// otherwise the directory the shell started in.
+    pub fn current_dir(&self) -> PathBuf {
+        self.foreground_pid()
+            .and_then(|pid| std::fs::read_link(format!("/proc/{}/cwd", pid)).ok())
+            .unwrap_or_else(|| self.start_dir.clone())
+    }
+
+    /// Reaps the shell if it has exited. The first time it notices, prints a notice into
+    /// the pane (the daemon prints its own) and returns the exit code; returns `None`
+    /// otherwise.
+    pub fn poll_exit(&mut self) -> Option<u32> {
+        if self.exit_code.is_some() {
+            return None;
+        }
+        let code = match &mut self.backend {
+            Backend::Local { child, .. } => {
+                let code = child.as_mut()?.try_wait().ok()??.exit_code();
+                self.parser.lock().unwrap()
+                    .process(format!("\r\n\x1b[0m[process exited with code {}]\r\n", code).as_bytes());
+                code
+            }
+            Backend::Daemon { exited, .. } => (*exited.lock().unwrap())?,
+        };
+        self.exit_code = Some(code);
+        Some(code)
+    }
+
     pub fn write(&mut self, data: &[u8]) -> Result<()> {
-        self.writer.write_all(data)?;
-        self.writer.flush()?;
+        match &mut self.backend {
+            Backend::Local { writer, .. } => {
+                writer.write_all(data)?;
+                writer.flush()?;
+            }
+            Backend::Daemon { stream, .. } => daemon::send_input(stream, data)?,
+        }
         Ok(())
     }
 
+    /// Resizes the PTY and the screen. Does nothing when the size is unchanged, so it is
+    /// cheap to call every frame and the program only gets SIGWINCH on real changes.
     pub fn resize(&mut self, width: u16, height: u16) -> Result<()> {
-        self.master.resize(PtySize {
-            rows: height,
-            cols: width,
-            pixel_width: 0,
-            pixel_height: 0,
-        })?;
-        self.parser.lock().unwrap().set_size(height, width);
+        if self.parser.lock().unwrap().screen().size() == (height, width) {
+            return Ok(());
+        }
+        match &mut self.backend {
+            Backend::Local { master, .. } => master.resize(PtySize {
+                rows: height,
+                cols: width,
+                pixel_width: 0,
+                pixel_height: 0,
+            })?,
+            Backend::Daemon { stream, .. } => {
+                daemon::send_request(stream, &Request::Resize { cols: width, rows: height })?
+            }
+        }
+        self.parser.lock().unwrap().screen_mut().set_size(height, width);
         Ok(())
     }
 }
 
+
+impl Drop for Session {
+    /// Hangs up on the shell (and with it, its jobs) so no process outlives its session,
+    /// unless it was detached into the daemon.
+    fn drop(&mut self) {
+        match &mut self.backend {
+            Backend::Local { child, .. } => {
+                let Some(mut child) = child.take() else { return };
+                let running = self.exit_code.is_none();
+                if running && let Some(pid) = child.process_id() {
+                    unsafe { libc::kill(pid as libc::pid_t, libc::SIGHUP) };
+                }
+                // `kill` waits a while before escalating to SIGKILL; keep that off the UI thread
+                std::thread::spawn(move || {
+                    if running {
+                        let _ = child.kill();
+                    }
+                    let _ = child.wait();
+                });
+            }
+            Backend::Daemon { stream, keep_alive, .. } => {
+                if !*keep_alive {
+                    let _ = daemon::send_request(stream, &Request::Kill);
+                }
+                let _ = stream.shutdown(std::net::Shutdown::Both);
+            }
+        }
+    }
+}
+
+/// The terminal tabs open on one tree row; `active` is the one shown and attached to.
+pub struct SessionTabs {
+    pub tabs: Vec<Session>,
+    pub active: usize,
+}
+
+impl SessionTabs {
+    pub fn new(session: Session) -> Self {
+        Self { tabs: vec![session], active: 0 }
+    }
+
+    pub fn active(&self) -> &Session {
+        &self.tabs[self.active]
+    }
+
+    pub fn active_mut(&mut self) -> &mut Session {
+        &mut self.tabs[self.active]
+    }
+
+    /// Adds a tab after the others and makes it active.
+    pub fn push(&mut self, session: Session) {
+        self.tabs.push(session);
+        self.active = self.tabs.len() - 1;
+    }
+
+    /// Closes tab `idx` (killing its process). Returns false once no tabs are left.
+    pub fn close(&mut self, idx: usize) -> bool {
+        if idx < self.tabs.len() {
+            self.tabs.remove(idx);
+        }
+        if idx < self.active || self.active >= self.tabs.len() {
+            self.active = self.active.saturating_sub(1);
+        }
+        !self.tabs.is_empty()
+    }
+
+    /// Moves `delta` tabs right (negative is left), wrapping around.
+    pub fn cycle(&mut self, delta: isize) {
+        let len = self.tabs.len() as isize;
+        self.active = (self.active as isize + delta).rem_euclid(len) as usize;
+    }
+}
+
+// ── Scrollback ────────────────────────────────────────────────────────────
+//
+// Lines are numbered oldest first across scrollback and the live screen, so line
+// `scrollback_len` is the top row of the live screen.
+
+/// Number of history lines above the live screen.
+pub fn scrollback_len(parser: &mut Parser) -> usize {
+    let current = parser.screen().scrollback();
+    parser.screen_mut().set_scrollback(usize::MAX);
+    let len = parser.screen().scrollback();
+    parser.screen_mut().set_scrollback(current);
+    len
+}
+
+/// Scrolls the view by `delta` lines (positive is older), clamped to the history.
+pub fn scroll_by(parser: &mut Parser, delta: isize) {
+    let current = parser.screen().scrollback();
+    parser.screen_mut().set_scrollback(current.saturating_add_signed(delta));
+}
+
+/// Line number of the top visible row.
+pub fn top_line(parser: &mut Parser) -> usize {
+    scrollback_len(parser) - parser.screen().scrollback()
+}
+
+/// Scrolls so `line` is the top row, or as close as the history allows.
+pub fn show_line(parser: &mut Parser, line: usize) {
+    let len = scrollback_len(parser);
+    parser.screen_mut().set_scrollback(len.saturating_sub(line));
+}
+
+/// Every line of the session, oldest first. Leaves the view where it was.
+pub fn history(parser: &mut Parser) -> Vec<String> {
+    let current = parser.screen().scrollback();
+    let len = scrollback_len(parser);
+    let (rows, cols) = parser.screen().size();
+    let mut lines: Vec<String> = Vec::with_capacity(len + rows as usize);
+
+    // Walk the history a screenful at a time, keeping only rows not seen yet
+    let mut offset = len;
+    loop {
+        parser.screen_mut().set_scrollback(offset);
+        let first = len - offset;
+        for (i, row) in parser.screen().rows(0, cols).enumerate() {
+            if first + i == lines.len() {
+                lines.push(row);
+            }
+        }
+        if offset == 0 {
+            break;
+        }
+        offset = offset.saturating_sub(rows as usize);
+    }
+
+    parser.screen_mut().set_scrollback(current);
+    lines
+}
+
+/// Finds the nearest line containing `query` (case-insensitive), starting at `from`
+/// and moving towards older lines when `older` is set, newer lines otherwise.
+pub fn find_line(lines: &[String], query: &str, from: usize, older: bool) -> Option<usize> {
+    let query = query.to_lowercase();
+    let hit = |i: &usize| lines[*i].to_lowercase().contains(&query);
+    if older {
+        (0..=from.min(lines.len().checked_sub(1)?)).rev().find(hit)
+    } else {
+        (from..lines.len()).find(hit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parser_with_lines(n: usize) -> Parser {
+        let mut parser = Parser::new(4, 20, 100);
+        for i in 0..n {
+            parser.process(format!("line {}\r\n", i).as_bytes());
+        }
+        parser
+    }
+
+    #[tokio::test]
+    async fn test_drop_hangs_up_without_blocking() {
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        let dir = std::env::temp_dir();
+        // Ignores the hangup, so only the background SIGKILL ends it
+        let command = Some("trap '' HUP; sleep 30".to_string());
+        let session = Session::new(dir, command, 40, 10, OutputNotifier::new(tx)).unwrap();
+        let Backend::Local { child: Some(child), .. } = &session.backend else { unreachable!() };
+        let pid = child.process_id().unwrap() as libc::pid_t;
+
+        let started = Instant::now();
+        drop(session);
+        assert!(started.elapsed() < std::time::Duration::from_millis(100));
+        for _ in 0..100 {
+            if unsafe { libc::kill(pid, 0) } != 0 {
+                return;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        panic!("the process outlived its session");
+    }
+
+    #[test]
+    fn test_scroll_and_show_line() {
+        let mut parser = parser_with_lines(10);
+        // 10 lines + the empty prompt line; 4 rows visible
+        assert_eq!(scrollback_len(&mut parser), 7);
+        scroll_by(&mut parser, 3);
+        assert_eq!(parser.screen().scrollback(), 3);
+        assert_eq!(top_line(&mut parser), 4);
+        scroll_by(&mut parser, 100);
+        assert_eq!(parser.screen().scrollback(), 7);
+        scroll_by(&mut parser, -100);
+        assert_eq!(parser.screen().scrollback(), 0);
+
+        show_line(&mut parser, 2);
+        assert_eq!(parser.screen().rows(0, 20).next().unwrap(), "line 2");
+    }
+
+    #[test]
+    fn test_history_and_find() {
+        let mut parser = parser_with_lines(10);
+        scroll_by(&mut parser, 2);
+        let lines = history(&mut parser);
+        assert_eq!(lines.len(), 11);
+        assert_eq!(lines[0], "line 0");
+        assert_eq!(lines[9], "line 9");
+        // The view is left untouched
+        assert_eq!(parser.screen().scrollback(), 2);
+
+        assert_eq!(find_line(&lines, "LINE 3", 9, true), Some(3));
+        assert_eq!(find_line(&lines, "line", 5, false), Some(5));
+        assert_eq!(find_line(&lines, "line 8", 5, true), None);
+        assert_eq!(find_line(&[], "x", 0, true), None);
+    }
+}
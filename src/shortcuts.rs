@@ -1,40 +1,43 @@
-pub const MAX_SHORTCUTS: usize = 5;
+use crate::actions::Action;
+use std::collections::HashSet;
+use std::hash::Hash;
 
-const fn ascii_lower(b: u8) -> u8 {
-    if b >= b'A' && b <= b'Z' { b + (b'a' - b'A') } else { b }
-}
+pub const MAX_SHORTCUTS: usize = 5;
 
+/// An action shown in the help bar. Its key comes from the keymap, so the bar follows
+/// rebinding; when the key is the label's first letter it is shown inline, `(t)erminal`.
 #[derive(Clone, Copy)]
 pub struct Shortcut {
-    pub key: char,
+    pub action: Action,
     pub label: &'static str,
 }
 
 impl Shortcut {
-    /// Key defaults to the first character of `label`, lowercased.
-    pub const fn new(label: &'static str) -> Self {
-        let key = ascii_lower(label.as_bytes()[0]) as char;
-        Self { key, label }
+    pub const fn new(action: Action, label: &'static str) -> Self {
+        Self { action, label }
     }
+}
 
-    /// Explicit key override for cases where the mnemonic doesn't lead the word
-    /// (e.g. `x` for "remove").
-    pub const fn with_key(key: char, label: &'static str) -> Self {
-        Self { key, label }
-    }
+/// The first key that appears more than once in `keys`. Keys bound in the same context
+/// must be unique; used by the keymap loader and the tests below.
+pub fn find_collision<K: Eq + Hash + Copy>(keys: impl IntoIterator<Item = K>) -> Option<K> {
+    let mut seen: HashSet<K> = HashSet::new();
+    keys.into_iter().find(|k| !seen.insert(*k))
 }
 
 // ── Shortcut groups ───────────────────────────────────────────────────────────
 //
 // Defined as const slices so the length can be checked at compile time.
 // The `const _: ()` assertions below are compile errors if any group exceeds
-// MAX_SHORTCUTS — adding a 6th entry will fail the build.
+// MAX_SHORTCUTS — adding a 6th entry will fail the build. Everything else is
+// reachable from the action palette.
 
 pub const PROJECT_SHORTCUTS: &[Shortcut] = &[
-    Shortcut::new("add repo"),      // a
-    Shortcut::new("terminal"),      // t
-    Shortcut::new("push all"),      // p
-    Shortcut::with_key('x', "remove"),
+    Shortcut::new(Action::AddRepo, "add repo"),
+    Shortcut::new(Action::Terminal, "terminal"),
+    Shortcut::new(Action::NewTab, "new tab"),
+    Shortcut::new(Action::Push, "push all"),
+    Shortcut::new(Action::Remove, "remove"),
 ];
 const _: () = assert!(
     PROJECT_SHORTCUTS.len() <= MAX_SHORTCUTS,
@@ -42,10 +45,11 @@ const _: () = assert!(
 );
 
 pub const WORKTREE_SHORTCUTS: &[Shortcut] = &[
-    Shortcut::new("terminal"),      // t
-    Shortcut::new("push"),          // p
-    Shortcut::new("diff"),          // d
-    Shortcut::with_key('x', "remove worktree"),
+    Shortcut::new(Action::Terminal, "terminal"),
+    Shortcut::new(Action::NewTab, "new tab"),
+    Shortcut::new(Action::Push, "push"),
+    Shortcut::new(Action::Diff, "diff"),
+    Shortcut::new(Action::Remove, "remove worktree"),
 ];
 const _: () = assert!(
     WORKTREE_SHORTCUTS.len() <= MAX_SHORTCUTS,
@@ -53,10 +57,11 @@ const _: () = assert!(
 );
 
 pub const GLOBAL_SHORTCUTS: &[Shortcut] = &[
-    Shortcut::new("new project"),   // n
-    Shortcut::new("options"),       // o
-    Shortcut::new("help"),          // h
-    Shortcut::new("quit"),          // q
+    Shortcut::new(Action::NewProject, "new project"),
+    Shortcut::new(Action::Options, "options"),
+    Shortcut::new(Action::Sessions, "sessions"),
+    Shortcut::new(Action::Help, "help"),
+    Shortcut::new(Action::Quit, "quit"),
 ];
 const _: () = assert!(
     GLOBAL_SHORTCUTS.len() <= MAX_SHORTCUTS,
@@ -68,16 +73,11 @@ const _: () = assert!(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::collections::HashSet;
 
     fn assert_no_collisions(context: &str, shortcuts: &[&Shortcut]) {
-        let mut seen: HashSet<char> = HashSet::new();
-        for s in shortcuts {
-            assert!(
-                seen.insert(s.key),
-                "Key collision in {context} context: '{}' is used more than once",
-                s.key
-            );
+        let keys = shortcuts.iter().map(|s| s.action.default_key());
+        if let Some(key) = find_collision(keys) {
+            panic!("Key collision in {context} context: '{key}' is used more than once");
         }
     }
 
@@ -92,6 +92,9 @@ mod tests {
         assert_no_collisions("worktree", &worktree_ctx);
 
         assert_no_collisions("global", &GLOBAL_SHORTCUTS.iter().collect::<Vec<_>>());
+
+        // Every Normal-mode action shares one context
+        assert_eq!(find_collision(Action::ALL.iter().map(|a| a.default_key())), None);
     }
 
     #[test]
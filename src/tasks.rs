@@ -0,0 +1,276 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::hooks::{AfterHooks, HookPoint};
+use crate::models::ProjectWorktree;
+use crate::multiplexer::Multiplexer;
+
+/// Progress reported by background tasks back to the main loop.
+pub enum TaskEvent {
+    /// One line of output, prefixed with `[label]` when shown.
+    Output { label: String, line: String },
+    /// A worktree setup run has finished.
+    SetupFinished { path: PathBuf, label: String, success: bool },
+    /// A terminal session printed something (or hung up); see `OutputNotifier`.
+    SessionOutput,
+    /// Fresh `git status` summaries, keyed by tree position and checked against the path
+    /// in case the tree changed while they were computed.
+    StatusRefreshed(Vec<((usize, usize), PathBuf, String)>),
+    /// The `workman-*` sessions the configured multiplexer currently has.
+    MultiplexerSessions(HashSet<String>),
+    /// A user command has finished in every worktree it ran in: `(label, success)` each.
+    CommandFinished { name: String, results: Vec<(String, bool)> },
+    /// A hook point's commands have run for each worktree, `(label, success)`; `then` is
+    /// the action that was waiting on them.
+    HooksFinished { point: HookPoint, results: Vec<(String, bool)>, then: AfterHooks },
+}
+
+/// One worktree a user command runs in.
+pub struct CommandRun {
+    pub label: String,
+    pub path: PathBuf,
+    pub command: String,
+    pub env: Vec<(String, String)>,
+}
+
+/// The hook commands one worktree runs at a hook point.
+pub struct HookJob {
+    pub label: String,
+    pub cwd: PathBuf,
+    pub commands: Vec<String>,
+    pub env: Vec<(String, String)>,
+}
+
+/// Lets PTY reader threads wake the main loop. Bursts of output are coalesced: after one
+/// `SessionOutput` is sent, no more are until the loop calls `clear` before redrawing.
+#[derive(Clone)]
+pub struct OutputNotifier {
+    pending: Arc<AtomicBool>,
+    tx: UnboundedSender<TaskEvent>,
+}
+
+impl OutputNotifier {
+    pub fn new(tx: UnboundedSender<TaskEvent>) -> Self {
+        Self { pending: Arc::new(AtomicBool::new(false)), tx }
+    }
+
+    pub fn notify(&self) {
+        if !self.pending.swap(true, Ordering::SeqCst) {
+            let _ = self.tx.send(TaskEvent::SessionOutput);
+        }
+    }
+
+    pub fn clear(&self) {
+        self.pending.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Runs `git status` for `worktrees` on a blocking thread and reports the results.
+pub fn spawn_status_refresh(worktrees: Vec<((usize, usize), ProjectWorktree)>, tx: UnboundedSender<TaskEvent>) {
+    tokio::task::spawn_blocking(move || {
+        let statuses = worktrees.into_iter()
+            .map(|(key, wt)| {
+                let status = wt.get_status();
+                (key, wt.path, status)
+            })
+            .collect();
+        let _ = tx.send(TaskEvent::StatusRefreshed(statuses));
+    });
+}
+
+/// Lists the multiplexer's workman sessions on a blocking thread and reports them.
+pub fn spawn_multiplexer_refresh(multiplexer: Multiplexer, tx: UnboundedSender<TaskEvent>) {
+    tokio::task::spawn_blocking(move || {
+        let _ = tx.send(TaskEvent::MultiplexerSessions(multiplexer.list_sessions()));
+    });
+}
+
+/// Runs `commands` one after another inside `path` with `env` set, streaming their output.
+/// Stops at the first command that fails.
+pub fn spawn_setup(
+    path: PathBuf,
+    label: String,
+    commands: Vec<String>,
+    env: Vec<(String, String)>,
+    tx: UnboundedSender<TaskEvent>,
+) {
+    tokio::spawn(async move {
+        let success = run_in_turn(&path, &commands, &env, &label, "", &tx).await;
+        let _ = tx.send(TaskEvent::SetupFinished { path, label, success });
+    });
+}
+
+/// Runs each job's commands for hook point `point`, streaming their output, and then hands
+/// `then` back with whether each job passed (after any `results` already known). A job
+/// stops at its first failing command; an all-or-nothing action stops the whole run.
+pub fn spawn_hooks(
+    point: HookPoint,
+    jobs: Vec<HookJob>,
+    mut results: Vec<(String, bool)>,
+    then: AfterHooks,
+    tx: UnboundedSender<TaskEvent>,
+) {
+    tokio::spawn(async move {
+        let prefix = format!("{}: ", point.name());
+        for job in jobs {
+            let success = run_in_turn(&job.cwd, &job.commands, &job.env, &job.label, &prefix, &tx).await;
+            results.push((job.label, success));
+            if !success && then.all_or_nothing() {
+                break;
+            }
+        }
+        let _ = tx.send(TaskEvent::HooksFinished { point, results, then });
+    });
+}
+
+/// Runs a user command in each of `runs` at the same time, streaming their output, then
+/// reports which succeeded.
+pub fn spawn_command(name: String, runs: Vec<CommandRun>, tx: UnboundedSender<TaskEvent>) {
+    tokio::spawn(async move {
+        let results = futures::future::join_all(runs.into_iter().map(|run| {
+            let tx = tx.clone();
+            async move {
+                let _ = tx.send(TaskEvent::Output { label: run.label.clone(), line: format!("$ {}", run.command) });
+                let success = match run_streaming(&run.path, &run.command, &run.env, &run.label, &tx).await {
+                    Ok(success) => success,
+                    Err(e) => {
+                        let _ = tx.send(TaskEvent::Output { label: run.label.clone(), line: format!("error: {}", e) });
+                        false
+                    }
+                };
+                (run.label, success)
+            }
+        }))
+        .await;
+        let _ = tx.send(TaskEvent::CommandFinished { name, results });
+    });
+}
+
+/// Runs `commands` one after another inside `path`, announcing each as `{prefix}$ command`.
+/// Stops at the first command that fails and returns whether all of them succeeded.
+async fn run_in_turn(
+    path: &Path,
+    commands: &[String],
+    env: &[(String, String)],
+    label: &str,
+    prefix: &str,
+    tx: &UnboundedSender<TaskEvent>,
+) -> bool {
+    for command in commands {
+        let _ = tx.send(TaskEvent::Output { label: label.to_string(), line: format!("{}$ {}", prefix, command) });
+        match run_streaming(path, command, env, label, tx).await {
+            Ok(true) => {}
+            Ok(false) => return false,
+            Err(e) => {
+                let _ = tx.send(TaskEvent::Output { label: label.to_string(), line: format!("{}error: {}", prefix, e) });
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// Runs a single shell command, forwarding stdout and stderr line by line.
+async fn run_streaming(
+    path: &Path,
+    command: &str,
+    env: &[(String, String)],
+    label: &str,
+    tx: &UnboundedSender<TaskEvent>,
+) -> std::io::Result<bool> {
+    let mut child = Command::new("sh")
+        .arg("-c").arg(command)
+        .current_dir(path)
+        .envs(env.iter().map(|(k, v)| (k, v)))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let stdout = child.stdout.take().map(|s| forward_lines(s, label.to_string(), tx.clone()));
+    let stderr = child.stderr.take().map(|s| forward_lines(s, label.to_string(), tx.clone()));
+    let status = child.wait().await?;
+    if let Some(handle) = stdout { let _ = handle.await; }
+    if let Some(handle) = stderr { let _ = handle.await; }
+
+    if !status.success() {
+        let code = status.code().map(|c| c.to_string()).unwrap_or_else(|| "signal".to_string());
+        let _ = tx.send(TaskEvent::Output { label: label.to_string(), line: format!("exited with {}", code) });
+    }
+    Ok(status.success())
+}
+
+fn forward_lines<R>(
+    reader: R,
+    label: String,
+    tx: UnboundedSender<TaskEvent>,
+) -> tokio::task::JoinHandle<()>
+where
+    R: tokio::io::AsyncRead + Unpin + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(reader).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            let _ = tx.send(TaskEvent::Output { label: label.clone(), line });
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::mpsc;
+
+    fn job(label: &str, cwd: &Path, commands: &[&str]) -> HookJob {
+        HookJob {
+            label: label.to_string(),
+            cwd: cwd.to_path_buf(),
+            commands: commands.iter().map(|c| c.to_string()).collect(),
+            env: vec![("WORKMAN_BRANCH".to_string(), "feat/x".to_string())],
+        }
+    }
+
+    /// Output lines and results of a hook run.
+    async fn finish(rx: &mut mpsc::UnboundedReceiver<TaskEvent>) -> (Vec<String>, Vec<(String, bool)>) {
+        let mut output = Vec::new();
+        while let Some(event) = rx.recv().await {
+            match event {
+                TaskEvent::Output { label, line } => output.push(format!("[{}] {}", label, line)),
+                TaskEvent::HooksFinished { results, .. } => return (output, results),
+                _ => {}
+            }
+        }
+        panic!("hook run never finished");
+    }
+
+    #[tokio::test]
+    async fn test_hooks_stop_at_first_failure() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let jobs = vec![
+            job("a", temp_dir.path(), &["echo $WORKMAN_BRANCH", "exit 3", "echo unreachable"]),
+            job("b", temp_dir.path(), &["true"]),
+        ];
+        spawn_hooks(HookPoint::PrePush, jobs, Vec::new(), AfterHooks::Report, tx.clone());
+        let (output, results) = finish(&mut rx).await;
+        assert!(output.contains(&"[a] pre_push: $ echo $WORKMAN_BRANCH".to_string()));
+        assert!(output.contains(&"[a] feat/x".to_string()));
+        assert!(output.contains(&"[a] exited with 3".to_string()));
+        assert!(!output.iter().any(|l| l.contains("unreachable")));
+        // Other worktrees' hooks still run
+        assert_eq!(results, vec![("a".to_string(), false), ("b".to_string(), true)]);
+
+        // Unless the action is all-or-nothing; known results come first
+        let jobs = vec![job("a", temp_dir.path(), &["false"]), job("b", temp_dir.path(), &["true"])];
+        let then = AfterHooks::RemoveProject { project: temp_dir.path().to_path_buf() };
+        spawn_hooks(HookPoint::PreRemove, jobs, vec![("c".to_string(), true)], then, tx);
+        let (_, results) = finish(&mut rx).await;
+        assert_eq!(results, vec![("c".to_string(), true), ("a".to_string(), false)]);
+    }
+}
@@ -1,58 +1,535 @@
 use crate::app::{App, InputMode};
-use crossterm::event::{self, KeyCode, KeyEvent};
+use crate::session;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use std::io::Write;
+use vt100::{MouseProtocolEncoding, MouseProtocolMode};
 
 pub fn handle_terminal_key_event(key: KeyEvent, app: &mut App) {
-    match key {
-        event::KeyEvent {
-            code: KeyCode::Char('c'),
-            modifiers: event::KeyModifiers::CONTROL,
-            ..
-        } => {
-            if let Some(sel) = app.get_selected_selection() {
-                if let Some(session) = app.sessions.get_mut(&sel) {
-                    let _ = session.write(&[3]); // Send ETX (Ctrl-C)
-                    app.terminal_warning = Some(
-                        "Ctrl-C sent. Use 'exit' or Ctrl-D to close the shell. Press Esc to detach."
-                            .to_string(),
-                    );
-                }
+    // After the process exits: Enter restarts it, anything else closes the tab
+    if let Some(sel) = app.get_selected_selection()
+        && let Some(tabs) = app.sessions.get(&sel)
+        && tabs.active().exit_code.is_some()
+    {
+        let (rows, cols) = tabs.active().parser.lock().unwrap().screen().size();
+        let active = tabs.active;
+        app.terminal_warning = None;
+        if key.code == KeyCode::Enter {
+            if let Err(e) = app.restart_session(sel, cols, rows) {
+                app.error_message = Some(format!("Failed to restart session: {}", e));
+                app.input_mode = InputMode::Normal;
+            }
+        } else {
+            app.close_session(sel, active);
+            if app.session(sel).is_none() {
+                app.input_mode = InputMode::Normal;
+            }
+        }
+        return;
+    }
+
+    if app.config.settings.detach_key().matches(&key) {
+        app.input_mode = InputMode::Normal;
+        return;
+    }
+    if key.code == KeyCode::PageUp && key.modifiers.contains(KeyModifiers::SHIFT) {
+        enter_scrollback(app);
+        return;
+    }
+    // Ctrl+PgUp / Ctrl+PgDn switch tabs, as in browsers
+    if matches!(key.code, KeyCode::PageUp | KeyCode::PageDown) && key.modifiers.contains(KeyModifiers::CONTROL) {
+        if let Some(sel) = app.get_selected_selection()
+            && let Some(tabs) = app.sessions.get_mut(&sel)
+        {
+            tabs.cycle(if key.code == KeyCode::PageDown { 1 } else { -1 });
+        }
+        return;
+    }
+
+    if let Some(sel) = app.get_selected_selection()
+        && let Some(session) = app.session_mut(sel)
+    {
+        let application_cursor = session.parser.lock().unwrap().screen().application_cursor();
+        let data = encode_key(&key, application_cursor);
+        if !data.is_empty() {
+            let _ = session.write(&data);
+        }
+    }
+}
+
+// ── Scrollback ────────────────────────────────────────────────────────────
+
+/// Switches to scrollback mode, one page up from the live screen.
+pub fn enter_scrollback(app: &mut App) {
+    if with_parser(app, |parser| {
+        let page = parser.screen().size().0 as isize;
+        session::scroll_by(parser, page);
+    })
+    .is_some()
+    {
+        app.scrollback_match = None;
+        app.input_mode = InputMode::Scrollback;
+    }
+}
+
+pub fn handle_scrollback_key_event(key: KeyEvent, app: &mut App) {
+    if app.config.settings.detach_key().matches(&key) {
+        leave_scrollback(app, InputMode::Normal);
+        return;
+    }
+    app.error_message = None;
+    let page = with_parser(app, |parser| parser.screen().size().0 as isize).unwrap_or(1);
+    let scroll = |app: &mut App, delta: isize| {
+        with_parser(app, |parser| session::scroll_by(parser, delta));
+    };
+    match key.code {
+        KeyCode::PageUp | KeyCode::Char('b') => scroll(app, page),
+        KeyCode::PageDown | KeyCode::Char(' ') => scroll(app, -page),
+        KeyCode::Up | KeyCode::Char('k') => scroll(app, 1),
+        KeyCode::Down | KeyCode::Char('j') => scroll(app, -1),
+        KeyCode::Home | KeyCode::Char('g') => scroll(app, isize::MAX),
+        KeyCode::End | KeyCode::Char('G') => scroll(app, isize::MIN),
+        KeyCode::Char('/') => {
+            app.input.clear();
+            app.input_mode = InputMode::ScrollbackSearch;
+        }
+        KeyCode::Char('n') => search_scrollback(app, true),
+        KeyCode::Char('N') => search_scrollback(app, false),
+        KeyCode::Esc | KeyCode::Char('q') => leave_scrollback(app, InputMode::Terminal),
+        _ => {}
+    }
+}
+
+pub fn handle_scrollback_search_key_event(key: KeyEvent, app: &mut App) {
+    match key.code {
+        KeyCode::Enter => {
+            let query = std::mem::take(&mut app.input);
+            app.input_mode = InputMode::Scrollback;
+            if !query.is_empty() {
+                app.scrollback_query = query;
+                app.scrollback_match = None;
+                search_scrollback(app, true);
             }
         }
-        event::KeyEvent {
-            code: KeyCode::Esc, ..
-        } => {
-            app.input_mode = InputMode::Normal;
-            app.terminal_warning = None; // Clear warning on detach
+        KeyCode::Char(c) => app.input.push(c),
+        KeyCode::Backspace => { app.input.pop(); }
+        KeyCode::Esc => {
+            app.input.clear();
+            app.input_mode = InputMode::Scrollback;
+        }
+        _ => {}
+    }
+}
+
+/// Scrolls three lines per wheel notch, entering scrollback from the live screen.
+pub fn handle_scrollback_wheel(up: bool, app: &mut App) {
+    if app.input_mode == InputMode::Terminal && !up {
+        return;
+    }
+    app.input_mode = InputMode::Scrollback;
+    with_parser(app, |parser| session::scroll_by(parser, if up { 3 } else { -3 }));
+}
+
+/// Jumps to the next match of `scrollback_query`, older or newer than the current one.
+fn search_scrollback(app: &mut App, older: bool) {
+    if app.scrollback_query.is_empty() {
+        return;
+    }
+    let query = app.scrollback_query.clone();
+    let previous = app.scrollback_match;
+    let found = with_parser(app, |parser| {
+        let lines = session::history(parser);
+        // Continue past the current match, else start from the top of the view
+        let from = match previous {
+            Some(line) if older => line.checked_sub(1)?,
+            Some(line) => line + 1,
+            None if older => session::top_line(parser) + parser.screen().size().0 as usize - 1,
+            None => session::top_line(parser),
+        };
+        let line = session::find_line(&lines, &query, from, older)?;
+        session::show_line(parser, line);
+        Some(line)
+    })
+    .flatten();
+
+    match found {
+        Some(line) => {
+            app.scrollback_match = Some(line);
+            app.error_message = None;
+        }
+        None => app.error_message = Some(format!("Pattern not found: {}", query)),
+    }
+}
+
+fn leave_scrollback(app: &mut App, mode: InputMode) {
+    with_parser(app, |parser| parser.screen_mut().set_scrollback(0));
+    app.scrollback_match = None;
+    app.error_message = None;
+    app.input_mode = mode;
+}
+
+/// Runs `f` against the selected session's parser, if there is one.
+fn with_parser<T>(app: &mut App, f: impl FnOnce(&mut vt100::Parser) -> T) -> Option<T> {
+    let sel = app.get_selected_selection()?;
+    let session = app.session(sel)?;
+    let mut parser = session.parser.lock().unwrap();
+    Some(f(&mut parser))
+}
+
+/// Sends pasted text to the attached session, bracketed when the child asked for it
+/// so editors don't auto-indent each line.
+pub fn handle_terminal_paste(text: &str, app: &mut App) {
+    if let Some(sel) = app.get_selected_selection()
+        && let Some(session) = app.session_mut(sel)
+    {
+        let bracketed = session.parser.lock().unwrap().screen().bracketed_paste();
+        let _ = session.write(&encode_paste(text, bracketed));
+    }
+}
+
+/// Forwards a mouse event to the attached session if the child enabled mouse reporting.
+/// Returns false when the child doesn't want it, so the caller can use it for selection.
+pub fn handle_terminal_mouse_event(mouse: MouseEvent, app: &mut App) -> bool {
+    let Some((col, row)) = pane_position(app, mouse.column, mouse.row) else {
+        return false;
+    };
+    let Some(sel) = app.get_selected_selection() else { return false };
+    let Some(session) = app.session_mut(sel) else { return false };
+
+    let (mode, encoding) = {
+        let parser = session.parser.lock().unwrap();
+        let screen = parser.screen();
+        (screen.mouse_protocol_mode(), screen.mouse_protocol_encoding())
+    };
+    if mode == MouseProtocolMode::None {
+        return false;
+    }
+    if let Some(data) = encode_mouse(&mouse, col, row, mode, encoding) {
+        let _ = session.write(&data);
+    }
+    true
+}
+
+/// Translates screen coordinates to a 0-based cell inside the output pane's border.
+pub fn pane_position(app: &App, column: u16, row: u16) -> Option<(u16, u16)> {
+    let area = app.output_area;
+    let inside = column > area.x && column < area.right().saturating_sub(1)
+        && row > area.y && row < area.bottom().saturating_sub(1);
+    inside.then(|| (column - area.x - 1, row - area.y - 1))
+}
+
+pub fn encode_paste(text: &str, bracketed: bool) -> Vec<u8> {
+    // Terminals send Enter as CR; a pasted end marker must not end the paste early
+    let body = text.replace("\r\n", "\r").replace('\n', "\r").replace("\x1b[201~", "");
+    if bracketed {
+        format!("\x1b[200~{}\x1b[201~", body).into_bytes()
+    } else {
+        body.into_bytes()
+    }
+}
+
+/// Encodes a mouse event in the child's requested protocol, or `None` when its mode
+/// doesn't report this kind of event. `col`/`row` are 0-based cells within the pane.
+pub fn encode_mouse(
+    mouse: &MouseEvent,
+    col: u16,
+    row: u16,
+    mode: MouseProtocolMode,
+    encoding: MouseProtocolEncoding,
+) -> Option<Vec<u8>> {
+    use MouseProtocolMode as M;
+    let button = |b: MouseButton| match b {
+        MouseButton::Left => 0,
+        MouseButton::Middle => 1,
+        MouseButton::Right => 2,
+    };
+    let (mut code, pressed, wanted) = match mouse.kind {
+        MouseEventKind::Down(b) => (button(b), true, mode != M::None),
+        MouseEventKind::Up(b) => (button(b), false, matches!(mode, M::PressRelease | M::ButtonMotion | M::AnyMotion)),
+        MouseEventKind::Drag(b) => (button(b) + 32, true, matches!(mode, M::ButtonMotion | M::AnyMotion)),
+        MouseEventKind::Moved => (3 + 32, true, mode == M::AnyMotion),
+        MouseEventKind::ScrollUp => (64, true, mode != M::None),
+        MouseEventKind::ScrollDown => (65, true, mode != M::None),
+        MouseEventKind::ScrollLeft => (66, true, mode != M::None),
+        MouseEventKind::ScrollRight => (67, true, mode != M::None),
+    };
+    if !wanted {
+        return None;
+    }
+    if mouse.modifiers.contains(KeyModifiers::SHIFT) { code += 4; }
+    if mouse.modifiers.contains(KeyModifiers::ALT) { code += 8; }
+    if mouse.modifiers.contains(KeyModifiers::CONTROL) { code += 16; }
+
+    let (x, y) = (u32::from(col) + 1, u32::from(row) + 1);
+    match encoding {
+        MouseProtocolEncoding::Sgr => {
+            Some(format!("\x1b[<{};{};{}{}", code, x, y, if pressed { 'M' } else { 'm' }).into_bytes())
         }
         _ => {
-            if let Some(sel) = app.get_selected_selection() {
-                if let Some(session) = app.sessions.get_mut(&sel) {
-                    // Clear warning on any other keypress
-                    if app.terminal_warning.is_some() {
-                        app.terminal_warning = None;
-                    }
-
-                    // Send key to PTY
-                    let data = match key.code {
-                        KeyCode::Char(c) => {
-                            let mut buf = [0u8; 4];
-                            c.encode_utf8(&mut buf).as_bytes().to_vec()
-                        }
-                        KeyCode::Enter => vec![b'\r'],
-                        KeyCode::Backspace => vec![8],
-                        KeyCode::Tab => vec![9],
-                        KeyCode::Up => vec![27, 91, 65],
-                        KeyCode::Down => vec![27, 91, 66],
-                        KeyCode::Right => vec![27, 91, 67],
-                        KeyCode::Left => vec![27, 91, 68],
-                        // Add more key codes as needed
-                        _ => Vec::new(), // Don't send unknown keys
-                    };
-                    if !data.is_empty() {
-                        let _ = session.write(&data);
-                    }
+            // Legacy encodings can't say which button was released
+            if !pressed {
+                code = (code & !0b11) | 3;
+            }
+            let mut data = vec![0x1b, b'[', b'M', 32 + code];
+            for v in [x, y] {
+                if encoding == MouseProtocolEncoding::Utf8 {
+                    let mut buf = [0u8; 4];
+                    data.extend_from_slice(char::from_u32(32 + v)?.encode_utf8(&mut buf).as_bytes());
+                } else {
+                    data.push(u8::try_from(32 + v).ok()?);
                 }
             }
+            Some(data)
         }
     }
 }
+
+// ── Mouse selection ───────────────────────────────────────────────────────
+
+/// A text selection over the session shown in the output pane, in pane cells.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TextSelection {
+    pub anchor: (u16, u16), // (row, col) where the drag started
+    pub head: (u16, u16),   // (row, col) under the pointer
+}
+
+impl TextSelection {
+    /// Start and end in reading order.
+    pub fn ordered(&self) -> ((u16, u16), (u16, u16)) {
+        if self.anchor <= self.head { (self.anchor, self.head) } else { (self.head, self.anchor) }
+    }
+
+    pub fn contains(&self, row: u16, col: u16) -> bool {
+        let (start, end) = self.ordered();
+        (row, col) >= start && (row, col) <= end
+    }
+}
+
+/// Drag in the output pane selects session text; releasing copies it to the clipboard.
+pub fn handle_selection_mouse_event(mouse: MouseEvent, app: &mut App) {
+    let pos = pane_position(app, mouse.column, mouse.row);
+    match mouse.kind {
+        MouseEventKind::Down(MouseButton::Left) => {
+            app.mouse_selection = pos.map(|(col, row)| TextSelection { anchor: (row, col), head: (row, col) });
+        }
+        MouseEventKind::Drag(MouseButton::Left) => {
+            let area = app.output_area;
+            if let Some(selection) = &mut app.mouse_selection
+                && area.width > 2
+                && area.height > 2
+            {
+                // Clamp to the pane so dragging past the edge still extends the selection
+                let col = mouse.column.clamp(area.x + 1, area.right().saturating_sub(2)) - area.x - 1;
+                let row = mouse.row.clamp(area.y + 1, area.bottom().saturating_sub(2)) - area.y - 1;
+                selection.head = (row, col);
+            }
+        }
+        MouseEventKind::Up(MouseButton::Left) => {
+            let Some(selection) = app.mouse_selection else { return };
+            if selection.anchor == selection.head {
+                app.mouse_selection = None;
+                return;
+            }
+            let Some(sel) = app.get_selected_selection() else { return };
+            let Some(session) = app.session(sel) else { return };
+            let ((start_row, start_col), (end_row, end_col)) = selection.ordered();
+            let text = session.parser.lock().unwrap().screen()
+                .contents_between(start_row, start_col, end_row, end_col + 1);
+            copy_to_clipboard(&text);
+            app.terminal_warning = Some(format!("Copied {} characters to the clipboard.", text.chars().count()));
+        }
+        _ => {}
+    }
+}
+
+/// Copies through the outer terminal with OSC 52, which also works over SSH.
+fn copy_to_clipboard(text: &str) {
+    let mut stdout = std::io::stdout();
+    let _ = write!(stdout, "\x1b]52;c;{}\x07", base64(text.as_bytes()));
+    let _ = stdout.flush();
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, &b)| n | u32::from(b) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Encodes a key event the way xterm would send it to the program in the PTY.
+/// `application_cursor` (DECCKM) switches unmodified arrows and Home/End to `ESC O` form.
+pub fn encode_key(key: &KeyEvent, application_cursor: bool) -> Vec<u8> {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    let alt = key.modifiers.contains(KeyModifiers::ALT);
+    // xterm modifier parameter: 1 + shift(1) + alt(2) + ctrl(4)
+    let modifier = 1
+        + key.modifiers.contains(KeyModifiers::SHIFT) as u8
+        + 2 * alt as u8
+        + 4 * ctrl as u8;
+
+    // Meta sends ESC before the plain encoding
+    let with_alt = |mut bytes: Vec<u8>| {
+        if alt {
+            bytes.insert(0, 0x1b);
+        }
+        bytes
+    };
+
+    match key.code {
+        KeyCode::Char(c) => {
+            if ctrl && let Some(b) = control_byte(c) {
+                return with_alt(vec![b]);
+            }
+            let mut buf = [0u8; 4];
+            with_alt(c.encode_utf8(&mut buf).as_bytes().to_vec())
+        }
+        KeyCode::Enter => with_alt(vec![b'\r']),
+        KeyCode::Tab => with_alt(vec![b'\t']),
+        KeyCode::BackTab => b"\x1b[Z".to_vec(),
+        KeyCode::Backspace => with_alt(vec![if ctrl { 0x08 } else { 0x7f }]),
+        KeyCode::Esc => with_alt(vec![0x1b]),
+        KeyCode::Null => vec![0],
+        KeyCode::Up => cursor_key(b'A', modifier, application_cursor),
+        KeyCode::Down => cursor_key(b'B', modifier, application_cursor),
+        KeyCode::Right => cursor_key(b'C', modifier, application_cursor),
+        KeyCode::Left => cursor_key(b'D', modifier, application_cursor),
+        KeyCode::Home => cursor_key(b'H', modifier, application_cursor),
+        KeyCode::End => cursor_key(b'F', modifier, application_cursor),
+        KeyCode::Insert => tilde_key(2, modifier),
+        KeyCode::Delete => tilde_key(3, modifier),
+        KeyCode::PageUp => tilde_key(5, modifier),
+        KeyCode::PageDown => tilde_key(6, modifier),
+        KeyCode::F(n @ 1..=4) => {
+            let final_byte = b'P' + (n - 1);
+            if modifier == 1 {
+                vec![0x1b, b'O', final_byte]
+            } else {
+                format!("\x1b[1;{}{}", modifier, final_byte as char).into_bytes()
+            }
+        }
+        KeyCode::F(n @ 5..=12) => {
+            const CODES: [u8; 8] = [15, 17, 18, 19, 20, 21, 23, 24];
+            tilde_key(CODES[(n - 5) as usize], modifier)
+        }
+        _ => Vec::new(), // Don't send keys xterm has no encoding for
+    }
+}
+
+/// Ctrl+key as a C0 control byte, following xterm's table.
+fn control_byte(c: char) -> Option<u8> {
+    match c.to_ascii_lowercase() {
+        c @ 'a'..='z' => Some(c as u8 - b'a' + 1),
+        '@' | ' ' | '2' => Some(0),
+        '[' | '3' => Some(0x1b),
+        '\\' | '4' => Some(0x1c),
+        ']' | '5' => Some(0x1d),
+        '^' | '6' => Some(0x1e),
+        '_' | '-' | '7' => Some(0x1f),
+        '?' | '8' => Some(0x7f),
+        _ => None,
+    }
+}
+
+fn cursor_key(final_byte: u8, modifier: u8, application_cursor: bool) -> Vec<u8> {
+    if modifier > 1 {
+        format!("\x1b[1;{}{}", modifier, final_byte as char).into_bytes()
+    } else if application_cursor {
+        vec![0x1b, b'O', final_byte]
+    } else {
+        vec![0x1b, b'[', final_byte]
+    }
+}
+
+fn tilde_key(code: u8, modifier: u8) -> Vec<u8> {
+    if modifier > 1 {
+        format!("\x1b[{};{}~", code, modifier).into_bytes()
+    } else {
+        format!("\x1b[{}~", code).into_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enc(code: KeyCode, modifiers: KeyModifiers) -> Vec<u8> {
+        encode_key(&KeyEvent::new(code, modifiers), false)
+    }
+
+    #[test]
+    fn test_encode_control_and_alt() {
+        assert_eq!(enc(KeyCode::Char('d'), KeyModifiers::CONTROL), vec![4]);
+        assert_eq!(enc(KeyCode::Char('r'), KeyModifiers::CONTROL), vec![18]);
+        assert_eq!(enc(KeyCode::Char('b'), KeyModifiers::ALT), b"\x1bb".to_vec());
+        assert_eq!(enc(KeyCode::Char('x'), KeyModifiers::CONTROL | KeyModifiers::ALT), vec![0x1b, 24]);
+        assert_eq!(enc(KeyCode::Char('é'), KeyModifiers::NONE), "é".as_bytes().to_vec());
+        assert_eq!(enc(KeyCode::Esc, KeyModifiers::NONE), vec![0x1b]);
+        assert_eq!(enc(KeyCode::Backspace, KeyModifiers::NONE), vec![0x7f]);
+    }
+
+    #[test]
+    fn test_encode_cursor_keys() {
+        assert_eq!(enc(KeyCode::Up, KeyModifiers::NONE), b"\x1b[A".to_vec());
+        assert_eq!(encode_key(&KeyEvent::new(KeyCode::Up, KeyModifiers::NONE), true), b"\x1bOA".to_vec());
+        assert_eq!(enc(KeyCode::Right, KeyModifiers::CONTROL), b"\x1b[1;5C".to_vec());
+        assert_eq!(enc(KeyCode::Home, KeyModifiers::NONE), b"\x1b[H".to_vec());
+        assert_eq!(enc(KeyCode::End, KeyModifiers::SHIFT), b"\x1b[1;2F".to_vec());
+    }
+
+    #[test]
+    fn test_encode_paste() {
+        assert_eq!(encode_paste("a\nb", false), b"a\rb".to_vec());
+        assert_eq!(encode_paste("a\r\nb", true), b"\x1b[200~a\rb\x1b[201~".to_vec());
+        assert_eq!(encode_paste("x\x1b[201~y", true), b"\x1b[200~xy\x1b[201~".to_vec());
+    }
+
+    #[test]
+    fn test_encode_mouse() {
+        let ev = |kind, modifiers| MouseEvent { kind, column: 0, row: 0, modifiers };
+        let down = ev(MouseEventKind::Down(MouseButton::Left), KeyModifiers::NONE);
+        let up = ev(MouseEventKind::Up(MouseButton::Left), KeyModifiers::NONE);
+        let drag = ev(MouseEventKind::Drag(MouseButton::Left), KeyModifiers::CONTROL);
+
+        assert_eq!(encode_mouse(&down, 4, 9, MouseProtocolMode::Press, MouseProtocolEncoding::Sgr),
+            Some(b"\x1b[<0;5;10M".to_vec()));
+        assert_eq!(encode_mouse(&up, 4, 9, MouseProtocolMode::Press, MouseProtocolEncoding::Sgr), None);
+        assert_eq!(encode_mouse(&up, 4, 9, MouseProtocolMode::PressRelease, MouseProtocolEncoding::Sgr),
+            Some(b"\x1b[<0;5;10m".to_vec()));
+        assert_eq!(encode_mouse(&up, 0, 0, MouseProtocolMode::PressRelease, MouseProtocolEncoding::Default),
+            Some(vec![0x1b, b'[', b'M', 32 + 3, 33, 33]));
+        assert_eq!(encode_mouse(&drag, 0, 0, MouseProtocolMode::ButtonMotion, MouseProtocolEncoding::Sgr),
+            Some(b"\x1b[<48;1;1M".to_vec()));
+        assert_eq!(encode_mouse(&down, 300, 0, MouseProtocolMode::Press, MouseProtocolEncoding::Default), None);
+    }
+
+    #[test]
+    fn test_selection_and_base64() {
+        let selection = TextSelection { anchor: (2, 5), head: (1, 3) };
+        assert_eq!(selection.ordered(), ((1, 3), (2, 5)));
+        assert!(selection.contains(1, 70));
+        assert!(!selection.contains(2, 6));
+        assert_eq!(base64(b"hi!"), "aGkh");
+        assert_eq!(base64(b"hello"), "aGVsbG8=");
+        assert_eq!(base64(b"h"), "aA==");
+    }
+
+    #[test]
+    fn test_encode_editing_and_function_keys() {
+        assert_eq!(enc(KeyCode::Delete, KeyModifiers::NONE), b"\x1b[3~".to_vec());
+        assert_eq!(enc(KeyCode::PageUp, KeyModifiers::NONE), b"\x1b[5~".to_vec());
+        assert_eq!(enc(KeyCode::PageDown, KeyModifiers::ALT), b"\x1b[6;3~".to_vec());
+        assert_eq!(enc(KeyCode::F(1), KeyModifiers::NONE), b"\x1bOP".to_vec());
+        assert_eq!(enc(KeyCode::F(2), KeyModifiers::SHIFT), b"\x1b[1;2Q".to_vec());
+        assert_eq!(enc(KeyCode::F(5), KeyModifiers::NONE), b"\x1b[15~".to_vec());
+        assert_eq!(enc(KeyCode::F(12), KeyModifiers::CONTROL), b"\x1b[24;5~".to_vec());
+        assert_eq!(enc(KeyCode::BackTab, KeyModifiers::SHIFT), b"\x1b[Z".to_vec());
+    }
+}
@@ -1,4 +1,9 @@
-use crate::app::{App, FuzzyEntry, InputMode, Selection};
+use crate::actions::Action;
+use crate::app::{App, FuzzyEntry, InputMode, OptionItem, Selection, TreeFilter};
+use crate::editor;
+use crate::keymap::Keymap;
+use crate::session;
+use crate::terminal_handler::TextSelection;
 use crate::shortcuts::{GLOBAL_SHORTCUTS, PROJECT_SHORTCUTS, WORKTREE_SHORTCUTS, Shortcut};
 use ratatui::{
     layout::{Constraint, Direction, Layout},
@@ -7,6 +12,7 @@ use ratatui::{
     text::{Line, Span},
 };
 use vt100::Color as Vt100Color;
+use std::borrow::Cow;
 
 pub fn ui(f: &mut ratatui::Frame, app: &mut App) {
     // ── Root layout: full-width help bar at top, content area below ──────
@@ -47,7 +53,8 @@ pub fn ui(f: &mut ratatui::Frame, app: &mut App) {
         .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
         .split(root_layout[1]);
 
-    let output_area = main_layout[1];
+    app.tree_area = main_layout[0];
+    app.panel_area = main_layout[1];
 
     // ── Left Panel: Project tree ─────────────────────────────────────────
     let items_with_data = app.get_tree_items();
@@ -58,8 +65,8 @@ pub fn ui(f: &mut ratatui::Frame, app: &mut App) {
 
     let tree_block = Block::default()
         .borders(Borders::ALL)
-        .title(" Projects ")
-        .border_style(if app.input_mode == InputMode::Normal {
+        .title(tree_title(&app.tree_filter))
+        .border_style(if matches!(app.input_mode, InputMode::Normal | InputMode::Filtering) {
             Style::default().fg(Color::Yellow)
         } else {
             Style::default()
@@ -73,17 +80,54 @@ pub fn ui(f: &mut ratatui::Frame, app: &mut App) {
 
     // ── Right Panel: Output / Terminal ───────────────────────────────────
     let pane_title = match app.input_mode {
-        InputMode::Terminal => " Terminal (Attached) ",
-        InputMode::AddingRepo => " Add Repo ",
-        InputMode::Options => " Options ",
-        InputMode::Help => " Help ",
-        _ => " Output ",
+        InputMode::Terminal => " Terminal (Attached) ".to_string(),
+        InputMode::Sessions | InputMode::RenamingSession => " Sessions ".to_string(),
+        InputMode::Commands => " Commands ".to_string(),
+        InputMode::Actions => " Actions ".to_string(),
+        InputMode::Scrollback | InputMode::ScrollbackSearch => match scrollback_position(app) {
+            Some((offset, len)) if offset > 0 => format!(" Terminal (Scrollback ↑{}/{}) ", offset, len),
+            _ => " Terminal (Scrollback — bottom) ".to_string(),
+        },
+        InputMode::AddingRepo => " Add Repo ".to_string(),
+        InputMode::Options | InputMode::EditingOption => " Options ".to_string(),
+        InputMode::Help => " Help ".to_string(),
+        _ => " Output ".to_string(),
     };
+    // Overlays take the whole panel; otherwise it is shared by the split panes
+    let overlay = matches!(
+        app.input_mode,
+        InputMode::AddingRepo | InputMode::Options | InputMode::EditingOption | InputMode::Help
+            | InputMode::Sessions | InputMode::RenamingSession | InputMode::Commands
+            | InputMode::Actions
+    );
+    let selected = app.get_selected_selection();
+    if let Some(sel) = selected {
+        app.panes.show(sel);
+    }
+    let mut output_area = app.panel_area;
+    let split = !overlay && app.panes.len() > 1;
+    if split {
+        for (i, (sel, area)) in app.panes.layout(app.panel_area).into_iter().enumerate() {
+            if i == app.panes.focused {
+                output_area = area;
+            } else {
+                render_unfocused_pane(f, app, sel, area);
+            }
+        }
+    }
+    app.output_area = output_area;
+
+    let mut title = vec![Span::raw(pane_title)];
+    if !overlay && let Some(sel) = selected {
+        title.extend(tab_bar(app, sel));
+    }
     let output_block = Block::default()
         .borders(Borders::ALL)
-        .title(pane_title)
+        .title(Line::from(title))
         .border_style(if app.input_mode != InputMode::Normal {
             Style::default().fg(Color::Yellow)
+        } else if split {
+            Style::default().fg(Color::Cyan)
         } else {
             Style::default()
         });
@@ -92,49 +136,47 @@ pub fn ui(f: &mut ratatui::Frame, app: &mut App) {
         render_add_repo(f, app, output_block, output_area);
         return;
     }
-    if app.input_mode == InputMode::Options {
+    if matches!(app.input_mode, InputMode::Options | InputMode::EditingOption) {
         render_options(f, app, output_block, output_area);
         return;
     }
     if app.input_mode == InputMode::Help {
-        render_help(f, output_block, output_area);
+        render_help(f, app, output_block, output_area);
+        return;
+    }
+    if matches!(app.input_mode, InputMode::Sessions | InputMode::RenamingSession) {
+        render_sessions(f, app, output_block, output_area);
+        return;
+    }
+    if app.input_mode == InputMode::Commands {
+        render_commands(f, app, output_block, output_area);
+        return;
+    }
+    if app.input_mode == InputMode::Actions {
+        render_actions(f, app, output_block, output_area);
         return;
     }
 
     // Terminal session rendering
-    let selected = app.tree_state.selected().and_then(|i| items_with_data.get(i).map(|item| item.1));
-    if let Some(sel) = selected {
-        if let Some(session) = app.sessions.get(&sel) {
-            let parser = session.parser.lock().unwrap();
-            let screen = parser.screen();
-            let (rows, cols) = screen.size();
-
-            let mut lines = Vec::new();
-            for row_idx in 0..rows {
-                let mut spans = Vec::new();
-                for col_idx in 0..cols {
-                    if let Some(cell) = screen.cell(row_idx, col_idx) {
-                        let mut style = Style::default();
-                        style = style.fg(map_vt100_color(cell.fgcolor()));
-                        style = style.bg(map_vt100_color(cell.bgcolor()));
-                        if cell.bold() { style = style.add_modifier(Modifier::BOLD); }
-                        if cell.italic() { style = style.add_modifier(Modifier::ITALIC); }
-                        if cell.underline() { style = style.add_modifier(Modifier::UNDERLINED); }
-                        spans.push(Span::styled(cell.contents(), style));
-                    } else {
-                        spans.push(Span::raw(" "));
-                    }
-                }
-                lines.push(Line::from(spans));
-            }
+    if let Some(sel) = selected
+        && let Some(session) = app.sessions.get(&sel).map(|tabs| tabs.active())
+    {
+        let parser = session.parser.lock().unwrap();
+        let screen = parser.screen();
+        let searching = matches!(app.input_mode, InputMode::Scrollback | InputMode::ScrollbackSearch)
+            && !app.scrollback_query.is_empty();
+        let query = searching.then_some(app.scrollback_query.as_str());
+        let lines = screen_lines(screen, app.mouse_selection, query);
 
-            let terminal_paragraph = Paragraph::new(lines).block(output_block);
-            f.render_widget(terminal_paragraph, output_area);
+        let terminal_paragraph = Paragraph::new(lines).block(output_block);
+        f.render_widget(terminal_paragraph, output_area);
 
+        // The cursor belongs to the live screen, not to history, and programs may hide it
+        if screen.scrollback() == 0 && !screen.hide_cursor() {
             let (cursor_row, cursor_col) = screen.cursor_position();
             f.set_cursor_position((output_area.x + 1 + cursor_col, output_area.y + 1 + cursor_row));
-            return;
         }
+        return;
     }
 
     // Standard output / input prompt rendering
@@ -176,10 +218,49 @@ pub fn ui(f: &mut ratatui::Frame, app: &mut App) {
                 Span::raw(app.input.as_str()),
                 Span::styled("_", Style::default().fg(Color::DarkGray)),
             ]));
+            // Live preview of the branch the name will produce
+            let preview = match app.derive_branch(app.input.trim()) {
+                Ok(branch) if branch.is_empty() => Span::styled("-", Style::default().fg(Color::DarkGray)),
+                Ok(branch) => Span::styled(branch, Style::default().fg(Color::Cyan)),
+                Err(e) => Span::styled(e.to_string(), Style::default().fg(Color::Red)),
+            };
+            output_lines.push(Line::from(vec![
+                Span::styled("        Branch: ", Style::default().fg(Color::DarkGray)),
+                preview,
+            ]));
+            if !app.config.templates.is_empty() {
+                output_lines.push(Line::from(""));
+                output_lines.push(Line::from(Span::styled("  Template:", Style::default().fg(Color::DarkGray))));
+                output_lines.push(template_line(app.template_cursor.is_none(), "(none)", "pick repos one by one".to_string()));
+                for (i, t) in app.config.templates.iter().enumerate() {
+                    let repos = t.repos.iter().map(|r| r.repo.as_str()).collect::<Vec<_>>().join(", ");
+                    output_lines.push(template_line(app.template_cursor == Some(i), &t.name, repos));
+                }
+            }
+        }
+        InputMode::EditingProjectBranch => {
+            output_lines.push(Line::from(""));
+            output_lines.push(Line::from(Span::styled(
+                format!("  Project: {}", app.pending_project_name),
+                Style::default().fg(Color::DarkGray),
+            )));
+            output_lines.push(Line::from(vec![
+                Span::styled("  Branch> ", Style::default().fg(Color::Yellow)),
+                Span::raw(app.input.as_str()),
+                Span::styled("_", Style::default().fg(Color::DarkGray)),
+            ]));
         }
         InputMode::EditingCommitMessage => {
             output_lines.push(Line::from(format!("  Commit msg> {}", app.input)));
         }
+        InputMode::NewTabCommand => {
+            output_lines.push(Line::from(""));
+            output_lines.push(Line::from(vec![
+                Span::styled("  Command> ", Style::default().fg(Color::Yellow)),
+                Span::raw(app.input.as_str()),
+                Span::styled("_", Style::default().fg(Color::DarkGray)),
+            ]));
+        }
         _ => {}
     }
 
@@ -207,28 +288,148 @@ pub fn ui(f: &mut ratatui::Frame, app: &mut App) {
     f.render_widget(output_paragraph, output_area);
 }
 
+/// Spans listing the terminal tabs open on `sel`, with the active one highlighted.
+fn tab_bar(app: &App, sel: Selection) -> Vec<Span<'static>> {
+    let Some(tabs) = app.sessions.get(&sel) else { return Vec::new() };
+    let mut spans = Vec::new();
+    for (i, session) in tabs.tabs.iter().enumerate() {
+        let style = if i == tabs.active {
+            Style::default().fg(Color::Black).bg(Color::Cyan).add_modifier(Modifier::BOLD)
+        } else if session.exit_code.is_some() {
+            Style::default().fg(Color::Red)
+        } else {
+            Style::default().fg(Color::DarkGray)
+        };
+        spans.push(Span::styled(format!(" {} {} ", i + 1, session.label()), style));
+        spans.push(Span::raw(" "));
+    }
+    spans
+}
+
+/// The session screen as styled lines, with the mouse selection reversed and the
+/// scrollback search `query` highlighted. Runs of cells with the same style share a span.
+fn screen_lines(
+    screen: &vt100::Screen,
+    selection: Option<TextSelection>,
+    query: Option<&str>,
+) -> Vec<Line<'static>> {
+    let (rows, cols) = screen.size();
+    let mut lines = Vec::new();
+    for row_idx in 0..rows {
+        let matches = query.map(|q| match_columns(screen, row_idx, q)).unwrap_or_default();
+        let mut spans: Vec<Span<'static>> = Vec::new();
+        let mut run = String::new();
+        let mut run_style = Style::default();
+        for col_idx in 0..cols {
+            let Some(cell) = screen.cell(row_idx, col_idx) else { continue };
+            // The right half of a wide character is drawn by its left half
+            if cell.is_wide_continuation() {
+                continue;
+            }
+            let selected = selection.is_some_and(|s| s.contains(row_idx, col_idx));
+            let mut style = cell_style(cell, selected);
+            if matches.get(col_idx as usize).copied().unwrap_or(false) {
+                style = style.fg(Color::Black).bg(Color::Yellow).remove_modifier(Modifier::REVERSED);
+            }
+            if style != run_style && !run.is_empty() {
+                spans.push(Span::styled(std::mem::take(&mut run), run_style));
+            }
+            run_style = style;
+            // Blank cells still take up their column; combining marks stay with their base
+            if cell.has_contents() {
+                run.push_str(cell.contents());
+            } else {
+                run.push(' ');
+            }
+        }
+        if !run.is_empty() {
+            spans.push(Span::styled(run, run_style));
+        }
+        lines.push(Line::from(spans));
+    }
+    lines
+}
+
+/// Maps a vt100 cell's colors and attributes to a ratatui style. `selected` flips
+/// reverse video, so inverse text stays readable inside a selection. (vt100 doesn't
+/// track strikethrough, so there is nothing to map for it.)
+fn cell_style(cell: &vt100::Cell, selected: bool) -> Style {
+    let mut style = Style::default()
+        .fg(map_vt100_color(cell.fgcolor()))
+        .bg(map_vt100_color(cell.bgcolor()));
+    if cell.bold() { style = style.add_modifier(Modifier::BOLD); }
+    if cell.dim() { style = style.add_modifier(Modifier::DIM); }
+    if cell.italic() { style = style.add_modifier(Modifier::ITALIC); }
+    if cell.underline() { style = style.add_modifier(Modifier::UNDERLINED); }
+    if cell.inverse() != selected { style = style.add_modifier(Modifier::REVERSED); }
+    style
+}
+
+/// A split pane other than the focused one: its row's active session, or a hint.
+fn render_unfocused_pane(f: &mut ratatui::Frame, app: &App, sel: Selection, area: ratatui::layout::Rect) {
+    let mut title = vec![Span::raw(format!(" {} ", app.tree_label(sel)))];
+    title.extend(tab_bar(app, sel));
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(Line::from(title))
+        .border_style(Style::default().fg(Color::DarkGray));
+
+    match app.session(sel) {
+        Some(session) => {
+            let parser = session.parser.lock().unwrap();
+            let lines = screen_lines(parser.screen(), None, None);
+            f.render_widget(Paragraph::new(lines).block(block), area);
+        }
+        None => {
+            let hint = Line::from(Span::styled(
+                "  No terminal. Focus this pane and press (t).",
+                Style::default().fg(Color::DarkGray),
+            ));
+            f.render_widget(Paragraph::new(hint).block(block).wrap(Wrap { trim: false }), area);
+        }
+    }
+}
+
+/// One row of the template list in the new-project wizard.
+fn template_line(selected: bool, name: &str, detail: String) -> Line<'static> {
+    let cursor = if selected { ">" } else { " " };
+    let style = if selected {
+        Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)
+    } else {
+        Style::default()
+    };
+    Line::from(vec![
+        Span::styled(format!("  {}  {}", cursor, name), style),
+        Span::styled(format!("  {}", detail), Style::default().fg(Color::DarkGray)),
+    ])
+}
+
 /// Renders a `Shortcut` as `(k)ey label` spans (no trailing padding — one per line).
-fn render_shortcut(s: &Shortcut) -> Vec<Span<'static>> {
+/// `(t)erminal` when the key is the label's first letter, else `(T) new tab`. Unbound
+/// actions are left out.
+fn render_shortcut(s: &Shortcut, keymap: &Keymap) -> Option<Line<'static>> {
     let ks = Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD);
-    let first = s.label.chars().next().map(|c| c.to_ascii_lowercase());
-    if first == Some(s.key) {
-        let rest = &s.label[s.key.len_utf8()..];
-        vec![
+    let key = keymap.hint(s.action);
+    if key.is_empty() {
+        return None;
+    }
+    let spans = match s.label.strip_prefix(key.as_str()) {
+        Some(rest) => vec![
             Span::raw("("),
-            Span::styled(s.key.to_string(), ks),
+            Span::styled(key, ks),
             Span::raw(format!("){rest}")),
-        ]
-    } else {
-        vec![
+        ],
+        None => vec![
             Span::raw("("),
-            Span::styled(s.key.to_string(), ks),
+            Span::styled(key, ks),
             Span::raw(format!(") {}", s.label)),
-        ]
-    }
+        ],
+    };
+    Some(Line::from(spans))
 }
 
 /// One line in the shortcuts column: bold key + dim label.
-fn named_key_line(key: &'static str, label: &'static str) -> Line<'static> {
+fn named_key_line(key: impl Into<Cow<'static, str>>, label: &'static str) -> Line<'static> {
     let ks = Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD);
     Line::from(vec![
         Span::styled(key, ks),
@@ -236,15 +437,68 @@ fn named_key_line(key: &'static str, label: &'static str) -> Line<'static> {
     ])
 }
 
+/// " Projects ", followed by the active tree filter.
+fn tree_title(filter: &TreeFilter) -> String {
+    if !filter.is_active() {
+        return " Projects ".to_string();
+    }
+    let mut title = " Projects —".to_string();
+    if !filter.query.is_empty() {
+        title.push_str(&format!(" /{}", filter.query));
+    }
+    if filter.changes_only {
+        title.push_str(" [changes]");
+    }
+    if filter.unpushed_only {
+        title.push_str(" [unpushed]");
+    }
+    title.push(' ');
+    title
+}
+
 /// Left column of the help bar: a few sentences describing the current context.
 fn context_description(app: &App) -> Vec<Line<'static>> {
     let dim = Style::default().fg(Color::DarkGray);
 
     // Terminal warning takes priority with a different colour
-    if app.input_mode == InputMode::Terminal {
-        if let Some(w) = &app.terminal_warning {
-            return vec![Line::from(Span::styled(w.clone(), Style::default().fg(Color::Yellow)))];
-        }
+    if matches!(app.input_mode, InputMode::Terminal | InputMode::Normal)
+        && let Some(w) = &app.terminal_warning
+    {
+        return vec![Line::from(Span::styled(w.clone(), Style::default().fg(Color::Yellow)))];
+    }
+
+    // Tree filter prompt
+    if app.input_mode == InputMode::Filtering {
+        return vec![
+            Line::from(
+                "Narrow the tree to projects and worktrees whose project name, branch or repo \
+                 name matches (fuzzy). Enter keeps the filter, Esc clears it.",
+            ),
+            Line::from(""),
+            Line::from(vec![
+                Span::styled("Filter> ", Style::default().fg(Color::Yellow)),
+                Span::raw(app.input.clone()),
+                Span::styled("_", dim),
+            ]),
+        ];
+    }
+
+    // Scrollback search prompt and its result
+    if app.input_mode == InputMode::ScrollbackSearch {
+        return vec![
+            Line::from("Search the session's history (case-insensitive). Enter jumps to the nearest older match."),
+            Line::from(""),
+            Line::from(vec![
+                Span::styled("Search> ", Style::default().fg(Color::Yellow)),
+                Span::raw(app.input.clone()),
+                Span::styled("_", dim),
+            ]),
+        ];
+    }
+    if app.input_mode == InputMode::Scrollback
+        && let Some(err) = &app.error_message
+    {
+        return vec![Line::from(Span::styled(err.clone(), Style::default().fg(Color::Yellow)))];
     }
 
     let text: String = match app.input_mode {
@@ -284,25 +538,37 @@ fn context_description(app: &App) -> Vec<Line<'static>> {
                 .to_string(),
         },
         InputMode::AddingProjectName => {
-            "New project. A project groups worktrees from different repos, all on the same branch \
-             — a temporary unit of work. Give it a short name; the branch is derived automatically."
+            if app.config.templates.is_empty() {
+                "New project. A project groups worktrees from different repos, all on the same branch \
+                 — a temporary unit of work. Give it a short name; the branch is derived from it \
+                 and can be edited next."
+                    .to_string()
+            } else {
+                "New project. Give it a short name; the branch is derived from it and can be edited next. \
+                 Pick a template to create all of its worktrees at once, or none to choose repos yourself."
+                    .to_string()
+            }
+        }
+        InputMode::EditingProjectBranch => {
+            "Branch for the new project. Every worktree in the project is created on it. \
+             Edit it if needed, then press Enter to create the project."
                 .to_string()
         }
         InputMode::AddingRepo => {
-            if let Some(p_idx) = app.adding_to_project {
-                if p_idx < app.config.projects.len() {
-                    let p = &app.config.projects[p_idx];
-                    return vec![Line::from(Span::styled(
-                        format!(
-                            "Adding repos to \"{}\" (branch: {}). \
-                             Each repo you add creates a worktree on that branch. \
-                             Type a path or pick from suggestions. \
-                             Press Enter on an empty line when done.",
-                            p.name, p.branch
-                        ),
-                        dim,
-                    ))];
-                }
+            if let Some(p_idx) = app.adding_to_project
+                && p_idx < app.config.projects.len()
+            {
+                let p = &app.config.projects[p_idx];
+                return vec![Line::from(Span::styled(
+                    format!(
+                        "Adding repos to \"{}\" (branch: {}). \
+                         Each repo you add creates a worktree on that branch. \
+                         Type a path or pick from suggestions. \
+                         Press Enter on an empty line when done.",
+                        p.name, p.branch
+                    ),
+                    dim,
+                ))];
             }
             "Adding a repo to the project. Type a path to a git repo.".to_string()
         }
@@ -311,16 +577,100 @@ fn context_description(app: &App) -> Vec<Line<'static>> {
              Staged and unstaged changes will be committed and pushed."
                 .to_string()
         }
+        InputMode::NewTabCommand => {
+            "Open another terminal tab here. Enter a command to run it in the tab (e.g. a dev \
+             server or test watcher), or leave it empty for a shell."
+                .to_string()
+        }
         InputMode::ViewingDiff => {
             "Viewing uncommitted changes in this worktree. Scroll with ↑↓. Press Esc to return."
                 .to_string()
         }
         InputMode::Terminal => {
-            "Terminal session active. Press Esc to detach — the session stays alive \
-             and can be re-attached."
+            format!(
+                "Terminal session active. Every key goes to the shell; press {} to detach — \
+                 the session stays alive and can be re-attached.",
+                app.config.settings.detach_key()
+            )
+        }
+        // Shown with its prompt above
+        InputMode::Filtering => "Filtering the tree.".to_string(),
+        InputMode::Scrollback | InputMode::ScrollbackSearch => {
+            if app.scrollback_query.is_empty() {
+                "Scrollback: paging through this session's history. The shell keeps running; \
+                 Esc returns to the live screen."
+                    .to_string()
+            } else {
+                format!(
+                    "Scrollback: matches for \"{}\" are highlighted. Use n / N to jump between them; \
+                     Esc returns to the live screen.",
+                    app.scrollback_query
+                )
+            }
+        }
+        InputMode::Sessions => {
+            "Every open terminal tab with what it is running and when it last printed. \
+             Jump to one to attach, or kill tabs you no longer need."
+                .to_string()
+        }
+        InputMode::Commands => match app.get_selected_selection() {
+            Some(Selection::Project(_)) => {
+                "Commands from Settings and the repos' .workman.toml. On a project they run in \
+                 every worktree that has them, side by side, with a summary at the end."
+                    .to_string()
+            }
+            _ => {
+                "Commands from Settings and the repo's .workman.toml. Output streams into this \
+                 panel, or into a new terminal tab for commands marked terminal = true."
+                    .to_string()
+            }
+        },
+        InputMode::Actions => {
+            "Everything you can do on the selected row. Type to narrow the list; letters \
+             match in order, so \"ntt\" finds New terminal tab."
+                .to_string()
+        }
+        InputMode::RenamingSession => {
+            "Give the tab a name for the tab bar and the switcher. Leave it empty to show its \
+             command instead."
                 .to_string()
         }
         InputMode::Options => "Settings. Changes are saved immediately.".to_string(),
+        InputMode::EditingOption => match OptionItem::ALL[app.options_cursor] {
+            OptionItem::WorktreeLocation => {
+                "Where new worktrees are created. Must contain {branch} and either {repo} or \
+                 {repo_path}; a leading ~ is your home folder. Clear it to restore the default."
+                    .to_string()
+            }
+            OptionItem::BranchTemplate => {
+                "How project names become branches. Use {slug}, {ticket} and {user}; \
+                 the ticket pattern, user and max length live in the config file. \
+                 Clear it to restore the default."
+                    .to_string()
+            }
+            OptionItem::DetachKey => {
+                "Key that leaves the embedded terminal, e.g. ctrl+], alt+q or f12. \
+                 Everything else, including Esc, goes to the shell. Clear it to restore the default."
+                    .to_string()
+            }
+            OptionItem::PersistentSessions => {
+                "Terminals run in a background session daemon, so they keep running after \
+                 workman quits and are reattached on the next start. Applies to new terminals."
+                    .to_string()
+            }
+            OptionItem::Multiplexer => {
+                "Built-in runs terminals inside workman. tmux, zellij and GNU screen give each \
+                 project and worktree a named session that t attaches to, or opens in place when \
+                 workman itself runs inside that multiplexer. Space cycles."
+                    .to_string()
+            }
+            OptionItem::Editor => {
+                "Command that e runs on the project folder or worktree, e.g. code, nvim or \
+                 idea {path}. The path is appended unless {path} marks where it goes. \
+                 Clear it to use $VISUAL or $EDITOR."
+                    .to_string()
+            }
+        },
         InputMode::Help => "Keybinding reference. Press any key to close.".to_string(),
         InputMode::ConfirmDelete => {
             let warn = Style::default().fg(Color::Yellow);
@@ -348,19 +698,31 @@ fn context_shortcut_lines(app: &App) -> Vec<Line<'static>> {
     match app.input_mode {
         InputMode::Normal => match app.get_selected_selection() {
             Some(Selection::Project(_)) => {
-                let mut lines = vec![named_key_line("Enter", "expand")];
-                lines.extend(PROJECT_SHORTCUTS.iter().map(|s| Line::from(render_shortcut(s))));
+                let mut lines = Vec::new();
+                if !app.tree_filter.is_active() {
+                    lines.push(named_key_line(app.keymap.hint(Action::ToggleExpand), "expand"));
+                }
+                lines.extend(PROJECT_SHORTCUTS.iter().filter_map(|s| render_shortcut(s, &app.keymap)));
                 lines
             }
             Some(Selection::Worktree(_, _)) => {
-                WORKTREE_SHORTCUTS.iter().map(|s| Line::from(render_shortcut(s))).collect()
+                WORKTREE_SHORTCUTS.iter().filter_map(|s| render_shortcut(s, &app.keymap)).collect()
             }
-            _ => GLOBAL_SHORTCUTS.iter().map(|s| Line::from(render_shortcut(s))).collect(),
+            _ => GLOBAL_SHORTCUTS.iter().filter_map(|s| render_shortcut(s, &app.keymap)).collect(),
         },
+        InputMode::AddingProjectName if !app.config.templates.is_empty() => vec![
+            named_key_line("Enter", "next"),
+            named_key_line("↑↓", "choose template"),
+            named_key_line("Esc", "cancel"),
+        ],
         InputMode::AddingProjectName => vec![
-            named_key_line("Enter", "create"),
+            named_key_line("Enter", "next"),
             named_key_line("Esc", "cancel"),
         ],
+        InputMode::EditingProjectBranch => vec![
+            named_key_line("Enter", "create"),
+            named_key_line("Esc", "back"),
+        ],
         InputMode::AddingRepo => vec![
             named_key_line("Enter", "add repo"),
             named_key_line("Enter", "(empty) done"),
@@ -368,7 +730,7 @@ fn context_shortcut_lines(app: &App) -> Vec<Line<'static>> {
             named_key_line("↑↓", "browse"),
             named_key_line("Esc", "cancel"),
         ],
-        InputMode::EditingCommitMessage => vec![
+        InputMode::EditingCommitMessage | InputMode::NewTabCommand => vec![
             named_key_line("Enter", "confirm"),
             named_key_line("Esc", "cancel"),
         ],
@@ -377,14 +739,60 @@ fn context_shortcut_lines(app: &App) -> Vec<Line<'static>> {
             named_key_line("Esc", "exit"),
         ],
         InputMode::Terminal => vec![
-            named_key_line("Esc", "detach"),
+            named_key_line(app.config.settings.detach_key().to_string(), "detach"),
+            named_key_line("Shift+PgUp", "scrollback"),
+            named_key_line("Ctrl+PgUp/PgDn", "switch tab"),
+            named_key_line("Alt+arrows", "focus pane"),
             named_key_line("Ctrl-B D", "tmux detach"),
         ],
+        InputMode::Scrollback => vec![
+            named_key_line("PgUp PgDn", "page"),
+            named_key_line("↑↓  Home End", "line / top / bottom"),
+            named_key_line("/", "search"),
+            named_key_line("n  N", "older / newer match"),
+            named_key_line("Esc", "back to live"),
+        ],
+        InputMode::ScrollbackSearch => vec![
+            named_key_line("Enter", "search"),
+            named_key_line("Esc", "cancel"),
+        ],
+        InputMode::Filtering => vec![
+            named_key_line("Enter", "keep filter"),
+            named_key_line("↑↓", "move"),
+            named_key_line("Esc", "clear"),
+        ],
+        InputMode::Sessions => vec![
+            named_key_line("Enter  1-9", "jump & attach"),
+            named_key_line("↑↓", "navigate"),
+            named_key_line("r", "rename"),
+            named_key_line("x", "kill"),
+            named_key_line("Esc", "close"),
+        ],
+        InputMode::Commands => vec![
+            named_key_line("Enter  1-9", "run"),
+            named_key_line("↑↓", "navigate"),
+            named_key_line("Esc", "close"),
+        ],
+        InputMode::Actions => vec![
+            named_key_line("Enter", "run"),
+            named_key_line("↑↓", "navigate"),
+            named_key_line("type", "filter"),
+            named_key_line("Esc", "close"),
+        ],
+        InputMode::RenamingSession => vec![
+            named_key_line("Enter", "save"),
+            named_key_line("Esc", "cancel"),
+        ],
         InputMode::Options => vec![
             named_key_line("↑↓", "navigate"),
             named_key_line("Space", "toggle"),
+            named_key_line("Enter", "edit"),
             named_key_line("Esc", "close"),
         ],
+        InputMode::EditingOption => vec![
+            named_key_line("Enter", "save"),
+            named_key_line("Esc", "cancel"),
+        ],
         InputMode::Help => vec![named_key_line("any key", "close")],
         InputMode::ConfirmDelete => vec![
             named_key_line("y / Enter", "confirm delete"),
@@ -403,14 +811,14 @@ fn render_add_repo(
     let dim = Style::default().fg(Color::DarkGray);
 
     // Context header
-    if let Some(p_idx) = app.adding_to_project {
-        if p_idx < app.config.projects.len() {
-            let p = &app.config.projects[p_idx];
-            lines.push(Line::from(Span::styled(
-                format!(" Adding to \"{}\"  branch: {}", p.name, p.branch),
-                Style::default().fg(Color::Cyan),
-            )));
-        }
+    if let Some(p_idx) = app.adding_to_project
+        && p_idx < app.config.projects.len()
+    {
+        let p = &app.config.projects[p_idx];
+        lines.push(Line::from(Span::styled(
+            format!(" Adding to \"{}\"  branch: {}", p.name, p.branch),
+            Style::default().fg(Color::Cyan),
+        )));
     }
     lines.push(Line::from(""));
 
@@ -477,6 +885,16 @@ fn render_add_repo(
         }
     }
 
+    // Hook and worktree setup output streams in while the picker stays open
+    if !app.command_output.is_empty() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled("  Output:", dim)));
+        let tail = app.command_output.len().saturating_sub(8);
+        for line in &app.command_output[tail..] {
+            lines.push(Line::from(Span::styled(format!("  {}", line), dim)));
+        }
+    }
+
     let paragraph = Paragraph::new(lines).block(block);
     f.render_widget(paragraph, area);
 }
@@ -491,24 +909,223 @@ fn render_options(
     lines.push(Line::from(Span::styled(" Settings", Style::default().add_modifier(Modifier::BOLD))));
     lines.push(Line::from(""));
 
-    let tmux_checked = if app.config.settings.use_tmux { "[x]" } else { "[ ]" };
-    let tmux_cursor = if app.options_cursor == 0 { "> " } else { "  " };
-    let tmux_style = if app.options_cursor == 0 {
-        Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)
-    } else {
-        Style::default()
-    };
-    lines.push(Line::from(Span::styled(
-        format!("{}{}  Use Tmux  (replaces built-in terminal with tmux sessions)", tmux_cursor, tmux_checked),
-        tmux_style,
-    )));
+    for (i, item) in OptionItem::ALL.iter().enumerate() {
+        let selected = app.options_cursor == i;
+        let cursor = if selected { "> " } else { "  " };
+        let style = if selected {
+            Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        let editing = selected && app.input_mode == InputMode::EditingOption;
+        match item {
+            OptionItem::Multiplexer => {
+                lines.push(Line::from(Span::styled(
+                    format!("{}Terminal backend  < {} >", cursor, app.config.settings.multiplexer().label()),
+                    style,
+                )));
+            }
+            OptionItem::PersistentSessions => {
+                let checked = if app.config.settings.persistent_sessions { "[x]" } else { "[ ]" };
+                lines.push(Line::from(Span::styled(
+                    format!("{}{}  Persistent sessions  (terminals survive quitting workman)", cursor, checked),
+                    style,
+                )));
+            }
+            OptionItem::WorktreeLocation => {
+                lines.push(Line::from(Span::styled(format!("{}Worktree location", cursor), style)));
+                lines.push(text_option_line(app, editing, app.config.settings.worktree_location()));
+            }
+            OptionItem::DetachKey => {
+                lines.push(Line::from(Span::styled(format!("{}Terminal detach key", cursor), style)));
+                let key = app.config.settings.detach_key().to_string();
+                lines.push(text_option_line(app, editing, &key));
+            }
+            OptionItem::BranchTemplate => {
+                lines.push(Line::from(Span::styled(format!("{}Branch template", cursor), style)));
+                lines.push(text_option_line(app, editing, app.config.settings.branch_naming.template()));
+            }
+            OptionItem::Editor => {
+                lines.push(Line::from(Span::styled(format!("{}Editor", cursor), style)));
+                let editor = editor::resolve(app.config.settings.editor.as_deref())
+                    .unwrap_or_else(|| "(not set)".to_string());
+                lines.push(text_option_line(app, editing, &editor));
+            }
+        }
+    }
+
+    if app.input_mode == InputMode::EditingOption
+        && let Some(err) = &app.error_message
+    {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(format!("  {}", err), Style::default().fg(Color::Yellow))));
+    }
+
+    let paragraph = Paragraph::new(lines).block(block);
+    f.render_widget(paragraph, area);
+}
+
+fn render_sessions(
+    f: &mut ratatui::Frame,
+    app: &App,
+    block: Block,
+    area: ratatui::layout::Rect,
+) {
+    let dim = Style::default().fg(Color::DarkGray);
+    let mut lines: Vec<Line> = Vec::new();
+    lines.push(Line::from(Span::styled(" Sessions", Style::default().add_modifier(Modifier::BOLD))));
+    lines.push(Line::from(""));
+
+    if app.session_entries.is_empty() {
+        lines.push(Line::from(Span::styled("  No sessions. Press (t) on a project or worktree to open one.", dim)));
+    }
+    for (i, entry) in app.session_entries.iter().enumerate() {
+        let Some(session) = app.sessions.get(&entry.sel).and_then(|tabs| tabs.tabs.get(entry.tab)) else { continue };
+        let selected = app.session_cursor == i;
+        let cursor = if selected { ">" } else { " " };
+        let style = if selected {
+            Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        let number = if i < 9 { format!("{}", i + 1) } else { " ".to_string() };
+
+        if selected && app.input_mode == InputMode::RenamingSession {
+            lines.push(Line::from(vec![
+                Span::styled(format!("{} {}  Name> ", cursor, number), Style::default().fg(Color::Yellow)),
+                Span::raw(app.input.as_str()),
+                Span::styled("_", dim),
+            ]));
+        } else {
+            lines.push(Line::from(Span::styled(
+                format!("{} {}  {}", cursor, number, app.session_label(entry.sel, entry.tab)),
+                style,
+            )));
+        }
+
+        let state = match session.exit_code {
+            Some(code) => Span::styled(format!("exited ({})", code), Style::default().fg(Color::Red)),
+            None => Span::styled(entry.command.clone(), Style::default().fg(Color::Green)),
+        };
+        lines.push(Line::from(vec![
+            Span::raw("      "),
+            state,
+            Span::styled(format!("  {}  ", format_idle(session.last_activity().elapsed())), dim),
+            Span::styled(entry.cwd.display().to_string(), dim),
+        ]));
+    }
+
+    let paragraph = Paragraph::new(lines).block(block);
+    f.render_widget(paragraph, area);
+}
+
+fn render_commands(
+    f: &mut ratatui::Frame,
+    app: &App,
+    block: Block,
+    area: ratatui::layout::Rect,
+) {
+    let dim = Style::default().fg(Color::DarkGray);
+    let mut lines: Vec<Line> = Vec::new();
+    lines.push(Line::from(Span::styled(" Commands", Style::default().add_modifier(Modifier::BOLD))));
+    lines.push(Line::from(""));
+
+    if app.palette_commands.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "  No commands. Add them under \"commands\" in ~/.workman.config or as [[commands]] in a repo's .workman.toml.",
+            dim,
+        )));
+    }
+    for (i, command) in app.palette_commands.iter().enumerate() {
+        let selected = app.palette_cursor == i;
+        let cursor = if selected { ">" } else { " " };
+        let style = if selected {
+            Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        let number = if i < 9 { format!("{}", i + 1) } else { " ".to_string() };
+        let mut line = vec![
+            Span::styled(format!("{} {}  {}", cursor, number, command.name), style),
+            Span::styled(format!("  {}", command.command), dim),
+        ];
+        if command.terminal {
+            line.push(Span::styled("  [terminal]", Style::default().fg(Color::Green)));
+        }
+        lines.push(Line::from(line));
+    }
 
     let paragraph = Paragraph::new(lines).block(block);
     f.render_widget(paragraph, area);
 }
 
+fn render_actions(
+    f: &mut ratatui::Frame,
+    app: &App,
+    block: Block,
+    area: ratatui::layout::Rect,
+) {
+    let dim = Style::default().fg(Color::DarkGray);
+    let sel = app.get_selected_selection();
+    let mut lines: Vec<Line> = Vec::new();
+    lines.push(Line::from(vec![
+        Span::styled(" > ", Style::default().fg(Color::Yellow)),
+        Span::raw(app.input.as_str()),
+        Span::styled("_", dim),
+    ]));
+    lines.push(Line::from(""));
+
+    if app.action_matches.is_empty() {
+        lines.push(Line::from(Span::styled("  No matching actions.", dim)));
+    }
+    // Keep the highlighted row on screen
+    let visible = area.height.saturating_sub(4).max(1) as usize;
+    let skip = app.action_cursor.saturating_sub(visible - 1);
+    for (i, action) in app.action_matches.iter().enumerate().skip(skip).take(visible) {
+        let selected = app.action_cursor == i;
+        let cursor = if selected { ">" } else { " " };
+        let style = if selected {
+            Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        lines.push(Line::from(vec![
+            Span::styled(format!("{}  {:40}", cursor, action.label(sel)), style),
+            Span::styled(app.keymap.hint(*action), Style::default().fg(Color::Yellow)),
+        ]));
+    }
+
+    let paragraph = Paragraph::new(lines).block(block);
+    f.render_widget(paragraph, area);
+}
+
+/// Short relative time for the session switcher, e.g. `just now`, `5m ago`.
+fn format_idle(idle: std::time::Duration) -> String {
+    match idle.as_secs() {
+        0..=4 => "just now".to_string(),
+        s @ 5..=59 => format!("{}s ago", s),
+        s @ 60..=3599 => format!("{}m ago", s / 60),
+        s @ 3600..=86399 => format!("{}h ago", s / 3600),
+        s => format!("{}d ago", s / 86400),
+    }
+}
+
+/// The value line under a text option: the live input while editing, else the saved value.
+fn text_option_line<'a>(app: &'a App, editing: bool, value: &str) -> Line<'a> {
+    if editing {
+        Line::from(vec![
+            Span::styled("      > ", Style::default().fg(Color::Yellow)),
+            Span::raw(app.input.as_str()),
+            Span::styled("_", Style::default().fg(Color::DarkGray)),
+        ])
+    } else {
+        Line::from(Span::styled(format!("      {}", value), Style::default().fg(Color::DarkGray)))
+    }
+}
+
 fn render_help(
     f: &mut ratatui::Frame,
+    app: &App,
     block: Block,
     area: ratatui::layout::Rect,
 ) {
@@ -526,34 +1143,70 @@ fn render_help(
         };
     }
 
+    // Keys follow the keymap file
+    let key = |action: Action| app.keymap.hint(action);
+    let pair = |a: Action, b: Action| format!("{} / {}", key(a), key(b));
+
     let lines: Vec<Line> = vec![
         Line::from(Span::styled(" Global", h)),
-        row!("q / Ctrl+C", "Quit"),
-        row!("↑ / ↓", "Navigate"),
-        row!("n", "(n)ew project"),
-        row!("o", "(o)ptions"),
-        row!("h", "(h)elp — this screen"),
-        row!("Ctrl+L", "Export log to /tmp/workman.log"),
+        row!(format!("{} / Ctrl+C", key(Action::Quit)), "Quit"),
+        row!(format!("↑ / ↓  {}", pair(Action::MoveDown, Action::MoveUp)), "Navigate"),
+        row!(key(Action::NewProject), "New project"),
+        row!(key(Action::Options), "Options"),
+        row!(key(Action::Sessions), "Sessions — switch, rename, kill"),
+        row!(key(Action::Palette), "Action palette — search everything by name"),
+        row!(key(Action::Help), "Help — this screen"),
+        row!(key(Action::ExportLog), "Export log to /tmp/workman.log"),
+        Line::from(""),
+        Line::from(Span::styled(" Moving around the tree", h)),
+        row!(pair(Action::First, Action::Last), "First / last row"),
+        row!(pair(Action::PageDown, Action::PageUp), "Page down / up"),
+        row!(pair(Action::Collapse, Action::Expand), "Collapse (or up to the project) / expand (or into it)"),
+        row!(pair(Action::NextProject, Action::PreviousProject), "Next / previous project"),
+        row!(key(Action::NextDirty), "Next worktree with changes"),
+        row!(key(Action::Filter), "Filter by project, branch or repo name (Enter keeps, Esc clears)"),
+        row!(pair(Action::FilterChanges, Action::FilterUnpushed), "Show only worktrees with changes / unpushed commits"),
+        row!("Esc", "Clear the filter"),
         Line::from(""),
         Line::from(Span::styled(" Project selected", h)),
-        row!("Enter", "Expand / collapse"),
-        row!("a", "(a)dd repo — creates worktree on project branch"),
-        row!("p", "(p)ush all worktrees"),
-        row!("t", "(t)erminal at project folder"),
-        row!("x", "(x) remove project and all its worktrees"),
+        row!(key(Action::ToggleExpand), "Expand / collapse"),
+        row!(key(Action::AddRepo), "Add repo — creates worktree on project branch"),
+        row!(key(Action::Push), "Push all worktrees"),
+        row!(key(Action::Terminal), "Terminal at project folder"),
+        row!(key(Action::NewTab), "New terminal tab, optionally running a command"),
+        row!(key(Action::Editor), "Open the project folder in the editor"),
+        row!(key(Action::RunCommand), "Run a command in every worktree"),
+        row!(pair(Action::PreviousTab, Action::NextTab), "Previous / next terminal tab"),
+        row!(key(Action::Remove), "Remove project and all its worktrees"),
         Line::from(""),
         Line::from(Span::styled(" Worktree selected", h)),
-        row!("t", "(t)erminal in worktree"),
-        row!("p", "(p)ush"),
-        row!("d", "(d)iff  (↑↓ scroll, Esc exit)"),
-        row!("x", "(x) remove worktree"),
+        row!(key(Action::Terminal), "Terminal in worktree"),
+        row!(key(Action::NewTab), "New terminal tab, optionally running a command"),
+        row!(key(Action::Editor), "Open the worktree in the editor"),
+        row!(key(Action::RunCommand), "Run a command from the palette"),
+        row!(pair(Action::PreviousTab, Action::NextTab), "Previous / next terminal tab"),
+        row!(key(Action::Push), "Push"),
+        row!(key(Action::Diff), "Diff  (↑↓ scroll, Esc exit)"),
+        row!(key(Action::Remove), "Remove worktree"),
         Line::from(""),
         Line::from(Span::styled(" Terminal (in-app PTY)", h)),
-        row!("Esc", "Detach — session stays alive"),
-        row!("Ctrl+C", "Send interrupt to shell"),
+        row!(app.config.settings.detach_key().to_string(), "Detach — session stays alive"),
+        row!("Shift+PgUp", "Scrollback (PgUp/PgDn, / search, n/N, Esc)"),
+        row!("Ctrl+PgUp/PgDn", "Previous / next tab"),
+        row!("Other keys", "Sent to the shell (Esc, Ctrl, Alt, F-keys, ...)"),
         Line::from(""),
-        Line::from(Span::styled(" Tmux mode (Use Tmux = on)", h)),
-        row!("Ctrl-B D", "Detach from tmux session"),
+        Line::from(Span::styled(" Split panes", h)),
+        row!(pair(Action::SplitSideBySide, Action::SplitStacked), "Split the focused pane side by side / stacked"),
+        row!(key(Action::ClosePane), "Close the focused pane"),
+        row!("Alt+arrows", "Move focus between panes (also while attached)"),
+        row!("Click", "Focus the pane under the mouse"),
+        Line::from(""),
+        Line::from(Span::styled(" tmux / zellij / screen backends", h)),
+        row!(key(Action::Terminal), "Attach the row's session; inside the multiplexer, open it in place"),
+        row!("Ctrl-B D", "Detach from tmux (workman resumes)"),
+        row!("Ctrl-B L", "Inside tmux: back to workman's session"),
+        row!("Ctrl-O D", "Detach from zellij"),
+        row!("Ctrl-A D", "Detach from screen"),
         Line::from(""),
         Line::from(Span::styled("  Press any key to close", dim)),
     ];
@@ -562,6 +1215,44 @@ fn render_help(
     f.render_widget(paragraph, area);
 }
 
+/// Lines scrolled back and total history of the selected session.
+fn scrollback_position(app: &App) -> Option<(usize, usize)> {
+    let sel = app.get_selected_selection()?;
+    let mut parser = app.session(sel)?.parser.lock().unwrap();
+    let offset = parser.screen().scrollback();
+    Some((offset, session::scrollback_len(&mut parser)))
+}
+
+/// Marks the columns of `row` covered by a case-insensitive match of `query`.
+fn match_columns(screen: &vt100::Screen, row: u16, query: &str) -> Vec<bool> {
+    let (_, cols) = screen.size();
+    // Lowercased text of the row, remembering which column each char came from
+    let mut text: Vec<char> = Vec::new();
+    let mut owner: Vec<u16> = Vec::new();
+    for col in 0..cols {
+        let contents = screen.cell(row, col).map(|c| c.contents().to_string()).unwrap_or_default();
+        let contents = if contents.is_empty() { " ".to_string() } else { contents };
+        for ch in contents.to_lowercase().chars() {
+            text.push(ch);
+            owner.push(col);
+        }
+    }
+
+    let query: Vec<char> = query.to_lowercase().chars().collect();
+    let mut marked = vec![false; cols as usize];
+    if query.is_empty() || query.len() > text.len() {
+        return marked;
+    }
+    for start in 0..=text.len() - query.len() {
+        if text[start..start + query.len()] == query[..] {
+            for &col in &owner[start..start + query.len()] {
+                marked[col as usize] = true;
+            }
+        }
+    }
+    marked
+}
+
 fn map_vt100_color(color: Vt100Color) -> Color {
     match color {
         Vt100Color::Default => Color::Reset,
@@ -569,3 +1260,31 @@ fn map_vt100_color(color: Vt100Color) -> Color {
         Vt100Color::Rgb(r, g, b) => Color::Rgb(r, g, b),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(bytes: &[u8]) -> Vec<Span<'static>> {
+        let mut parser = vt100::Parser::new(2, 10, 0);
+        parser.process(bytes);
+        screen_lines(parser.screen(), None, None).remove(0).spans
+    }
+
+    #[test]
+    fn test_screen_lines_merge_runs_and_attributes() {
+        let spans = render(b"ab\x1b[7;2mcd\x1b[0m");
+        let texts: Vec<&str> = spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(texts, vec!["ab", "cd", "      "]);
+        assert!(spans[1].style.add_modifier.contains(Modifier::REVERSED | Modifier::DIM));
+        assert!(!spans[2].style.add_modifier.contains(Modifier::REVERSED));
+    }
+
+    #[test]
+    fn test_screen_lines_wide_and_combining_chars() {
+        // A wide character fills two columns; the gap left by cursor movement stays blank
+        let spans = render("中e\u{301}\x1b[2Cx".as_bytes());
+        let text: String = spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(text, "中e\u{301}  x    ");
+    }
+}